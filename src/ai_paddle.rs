@@ -1,8 +1,115 @@
-use std::time::Duration;
+use std::{str::FromStr, time::Duration};
 
 use bevy::prelude::*;
+use bevy_turborand::*;
+use serde::Serialize;
 
-use crate::{ball::Ball, paddle::Paddle, player::Player, GameSet, PongPlugin, WINDOW_WIDTH};
+use crate::{
+	config::GameConfig,
+	paddle::{Paddle, PaddleInput},
+	player::Player,
+	reset::Reset,
+	simulation::{Phase, Simulation, SimulationPlugin},
+	GameSet, WINDOW_HEIGHT,
+};
+
+/// Difficulty tiers a bot can be configured with.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+pub enum AiDifficulty {
+	Easy,
+	#[default]
+	Normal,
+	Hard,
+	Perfect,
+}
+
+impl AiDifficulty {
+	pub fn profile(&self) -> AiProfile {
+		match self {
+			AiDifficulty::Easy => AiProfile {
+				reaction_time: 0.35,
+				prediction_error: 60.,
+				max_acceleration: 1500.,
+				aim: 0.,
+			},
+			AiDifficulty::Normal => AiProfile {
+				reaction_time: 0.2,
+				prediction_error: 30.,
+				max_acceleration: 3000.,
+				aim: 0.4,
+			},
+			AiDifficulty::Hard => AiProfile {
+				reaction_time: 0.1,
+				prediction_error: 10.,
+				max_acceleration: 6000.,
+				aim: 0.75,
+			},
+			AiDifficulty::Perfect => AiProfile {
+				reaction_time: 0.,
+				prediction_error: 0.,
+				max_acceleration: f32::INFINITY,
+				aim: 0.9,
+			},
+		}
+	}
+}
+
+impl FromStr for AiDifficulty {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name.to_lowercase().as_str() {
+			"easy" => Ok(AiDifficulty::Easy),
+			"normal" => Ok(AiDifficulty::Normal),
+			"hard" => Ok(AiDifficulty::Hard),
+			"perfect" => Ok(AiDifficulty::Perfect),
+			_ => Err(format!(
+				"unknown difficulty `{}`, expected easy, normal, hard or perfect",
+				name
+			)),
+		}
+	}
+}
+
+/// Describes how a bot plays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AiProfile {
+	/// Seconds the bot needs to notice that the ball changed direction.
+	pub reaction_time: f32,
+	/// Maximum number of pixels the predicted intercept is off by.
+	pub prediction_error: f32,
+	/// Maximum change of the paddle's velocity per second.
+	pub max_acceleration: f32,
+	/// How far from the paddle's centre the bot tries to hit the ball, from `0.` (centre) to `1.` (edge).
+	/// Hits further out produce steeper returns, see [`crate::ball::BallPlugin::calculate_bounce_angle`].
+	pub aim: f32,
+}
+
+/// State of the bot driving a paddle, see [`crate::controller::Controller::Ai`].
+#[derive(Component, Debug)]
+pub struct AiPaddle {
+	pub difficulty: AiDifficulty,
+	pub profile: AiProfile,
+	reaction: Timer,
+	/// The ball's horizontal heading the current plan was made for, see [`AiPaddlePlugin::heading`].
+	planned_for: Option<i8>,
+	target_y: f32,
+	serve_delay: Timer,
+}
+
+impl AiPaddle {
+	pub fn new(difficulty: AiDifficulty) -> Self {
+		let profile = difficulty.profile();
+		Self {
+			difficulty,
+			profile,
+			reaction: Timer::from_seconds(profile.reaction_time, TimerMode::Once),
+			planned_for: None,
+			target_y: 0.,
+			serve_delay: Timer::from_seconds(AiPaddlePlugin::SERVE_DELAY, TimerMode::Once),
+		}
+	}
+}
 
 pub struct AiPaddlePlugin;
 impl Plugin for AiPaddlePlugin {
@@ -11,8 +118,8 @@ impl Plugin for AiPaddlePlugin {
 			//.add_system(Self::debug_print.run_if(PongPlugin::in_menu))
 			.add_system(
 				Self::process_player
-					.in_set(GameSet::Movement)
-					.run_if(PongPlugin::in_menu)
+					.in_set(GameSet::Input)
+					.run_if(SimulationPlugin::is_simulating)
 					.in_schedule(CoreSchedule::FixedUpdate),
 			);
 	}
@@ -24,6 +131,11 @@ struct DebugPrintConfig {
 }
 
 impl AiPaddlePlugin {
+	/// How close (in pixels) the paddle centre has to be to its target before it stops moving.
+	const DEAD_ZONE: f32 = 2.0;
+	/// Seconds a bot waits before serving.
+	const SERVE_DELAY: f32 = 0.6;
+
 	// fn debug_print(query: Query<(&Paddle, &Player)>, time: Res<Time>, mut config: ResMut<DebugPrintConfig>) {
 	// 	config.timer.tick(time.delta());
 
@@ -44,41 +156,194 @@ impl AiPaddlePlugin {
 	}
 
 	fn process_player(
-		mut paddle_query: Query<(&Transform, &mut Paddle, &Player), With<Paddle>>,
-		mut ball_query: Query<(&Transform, &Ball)>,
+		mut ai_query: Query<(&mut PaddleInput, &Player, &mut AiPaddle)>,
+		mut reset_reader: EventReader<Reset>,
+		mut rng: ResMut<GlobalRng>,
+		simulation: Res<Simulation>,
+		time: Res<FixedTime>,
 	) {
-		for (ball_transform, ball) in ball_query.iter_mut() {
-			for (paddle_transform, mut paddle, player) in paddle_query.iter_mut() {
-				let ball_x = ball_transform.translation.x;
-				let ball_y = ball_transform.translation.y;
-				let paddle_y = paddle_transform.translation.y;
-				let ball_vx = ball.velocity().x;
-				let ball_vy = ball.velocity().y;
-
-				let ball_is_moving_towards_player = match player {
-					&Player::Left => ball_vx < 0. && ball_x < WINDOW_WIDTH * 0.25,
-					&Player::Right => ball_vx > 0. && ball_x > -WINDOW_WIDTH * 0.25,
-				};
+		let state = &simulation.state;
+		let config = &simulation.settings.config;
+		let was_reset = reset_reader.iter().last().is_some();
+		let step = time.period;
+		let ball_position = state.ball.position;
+		let ball_velocity = state.ball.velocity();
 
-				if ball_is_moving_towards_player {
-					if ball_y != paddle_y {
-						let time_til_collision = match player {
-							&Player::Left => (Paddle::MARGIN + Paddle::WIDTH + ball_x) / ball_vx,
-							&Player::Right => (WINDOW_WIDTH - Paddle::MARGIN - Paddle::WIDTH - ball_x) / ball_vx,
-						};
-						let desired_distance = paddle_y - ball_y;
-						let desired_velocity = -desired_distance;
-
-						paddle.velocity.y = ball.velocity().y.clamp(-paddle.speed, paddle.speed);
-					} else {
-						debug!("Paddle is already centered at the ball");
-						paddle.velocity.y = 0.;
-					}
-				} else {
-					// debug!("Ball is moving away from the paddle ({:?})", player);
-					paddle.velocity.y = 0.;
+		for (mut input, player, mut ai_paddle) in ai_query.iter_mut() {
+			let profile = ai_paddle.profile;
+			let paddle = state.paddles[player.index()];
+			let serving = state.phase == Phase::Serve && state.server.player == *player;
+
+			if serving {
+				// Move somewhere random and serve while still moving, so the serve gets an angle
+				if ai_paddle.serve_delay.elapsed().is_zero() {
+					ai_paddle.target_y = rng.f32_normalized() * config.paddle_height;
 				}
+				input.serve = ai_paddle.serve_delay.tick(step).finished();
+			} else {
+				ai_paddle.serve_delay.reset();
+				input.serve = false;
 			}
+
+			// Paddle hits and serves change the ball's horizontal heading, wall bounces don't
+			let heading = Self::heading(ball_velocity);
+			if was_reset || ai_paddle.planned_for != Some(heading) {
+				ai_paddle.planned_for = Some(heading);
+				ai_paddle.reaction.reset();
+			}
+
+			if ai_paddle.reaction.tick(step).just_finished() && !serving {
+				let opponent_y = state.paddles[player.opponent().index()].y;
+				let contact_x = Self::contact_x(*player, config);
+
+				ai_paddle.target_y = match Self::predict_intercept(ball_position, ball_velocity, contact_x, config) {
+					Some(intercept_y) => {
+						let noise = rng.f32_normalized() * profile.prediction_error;
+						// Hitting the ball above the paddle's centre sends it upwards, so aim away from the opponent
+						let aim_offset = profile.aim * (paddle.height(config) - config.wall_width) / 2.;
+						let hit_offset = if opponent_y > 0. { -aim_offset } else { aim_offset };
+
+						intercept_y + noise - hit_offset
+					}
+					// Ball is moving away, drift back to the centre
+					None => 0.,
+				};
+			}
+
+			let distance = ai_paddle.target_y - paddle.y;
+			let desired_velocity = if distance.abs() <= Self::DEAD_ZONE {
+				0.
+			} else {
+				(distance / step.as_secs_f32()).clamp(-paddle.speed, paddle.speed)
+			};
+
+			let max_delta = profile.max_acceleration * step.as_secs_f32();
+			let velocity = paddle.velocity + (desired_velocity - paddle.velocity).clamp(-max_delta, max_delta);
+			input.axis = velocity / paddle.speed;
+		}
+	}
+
+	/// Whether the ball moves to the right (`1`), to the left (`-1`) or not at all (`0`).
+	fn heading(velocity: Vec2) -> i8 {
+		if velocity.x > 0. {
+			1
+		} else if velocity.x < 0. {
+			-1
+		} else {
+			0
+		}
+	}
+
+	/// The x coordinate the ball's centre has when it touches the face of `player`'s paddle.
+	fn contact_x(player: Player, config: &GameConfig) -> f32 {
+		// Half the paddle and half the ball, which are both as wide as the walls
+		let offset = config.wall_width;
+		match player {
+			Player::Left => Paddle::x(player, config) + offset,
+			Player::Right => Paddle::x(player, config) - offset,
+		}
+	}
+
+	/// Predicts the y coordinate at which a ball at `position` moving with `velocity` crosses `contact_x`,
+	/// taking reflections off [`crate::wall::Wall::Top`] and [`crate::wall::Wall::Bottom`] into account.
+	/// Returns `None` if the ball is moving away from `contact_x`.
+	pub fn predict_intercept(position: Vec2, velocity: Vec2, contact_x: f32, config: &GameConfig) -> Option<f32> {
+		if velocity.x == 0. {
+			return None;
+		}
+
+		let time_til_collision = (contact_x - position.x) / velocity.x;
+		if time_til_collision < 0. {
+			return None;
+		}
+
+		let unfolded_y = position.y + velocity.y * time_til_collision;
+		Some(Self::fold_between_walls(unfolded_y, config.wall_width))
+	}
+
+	/// Mirrors an unbounded y coordinate back into the playing field, as if the ball had bounced off the walls.
+	fn fold_between_walls(y: f32, wall_width: f32) -> f32 {
+		// The ball is as wide as a wall, so its centre turns around one wall width away from the wall's centre
+		let max_y = WINDOW_HEIGHT / 2. - wall_width * 1.5;
+		let min_y = -max_y;
+		let span = max_y - min_y;
+
+		let folded = (y - min_y).rem_euclid(2. * span);
+		if folded > span {
+			max_y - (folded - span)
+		} else {
+			min_y + folded
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// How far from the walls the ball's centre turns around with the default config.
+	const MAX_Y: f32 = WINDOW_HEIGHT / 2. - 30.;
+
+	fn predict(position: Vec2, velocity: Vec2, contact_x: f32) -> Option<f32> {
+		AiPaddlePlugin::predict_intercept(position, velocity, contact_x, &GameConfig::default())
+	}
+
+	fn assert_near(actual: Option<f32>, expected: f32) {
+		let actual = actual.expect("the ball should reach the paddle");
+		assert!(
+			(actual - expected).abs() < 1e-3,
+			"expected {}, got {}",
+			expected,
+			actual
+		);
+	}
+
+	#[test]
+	fn a_ball_moving_straight_stays_at_its_height() {
+		assert_near(predict(Vec2::new(0., 50.), Vec2::new(400., 0.), 600.), 50.);
+	}
+
+	#[test]
+	fn a_ball_that_reaches_the_paddle_first_doesnt_bounce() {
+		assert_near(predict(Vec2::ZERO, Vec2::new(400., 100.), 400.), 100.);
+		assert_near(predict(Vec2::ZERO, Vec2::new(-400., -100.), -400.), -100.);
+	}
+
+	#[test]
+	fn a_ball_bounces_off_the_top_and_the_bottom() {
+		// 70 pixels past the top, so 70 pixels back down from it
+		assert_near(predict(Vec2::new(0., 300.), Vec2::new(100., 100.), 100.), MAX_Y - 70.);
+		assert_near(
+			predict(Vec2::new(0., -300.), Vec2::new(100., -100.), 100.),
+			-MAX_Y + 70.,
+		);
+	}
+
+	#[test]
+	fn a_ball_can_bounce_more_than_once() {
+		// Up to the top, all the way down to the bottom and 10 pixels back up
+		let distance = 3. * MAX_Y + 10.;
+		assert_near(predict(Vec2::ZERO, Vec2::new(100., 100.), distance), -MAX_Y + 10.);
+	}
+
+	#[test]
+	fn a_ball_moving_away_is_not_intercepted() {
+		assert_eq!(predict(Vec2::ZERO, Vec2::new(-400., 100.), 600.), None);
+		assert_eq!(predict(Vec2::new(700., 0.), Vec2::new(400., 100.), 600.), None);
+		assert_eq!(predict(Vec2::ZERO, Vec2::new(0., 100.), 600.), None);
+	}
+
+	#[test]
+	fn folding_keeps_heights_on_the_field() {
+		let wall_width = GameConfig::default().wall_width;
+		for y in [-MAX_Y, -123., 0., 45.5, MAX_Y] {
+			assert!(
+				(AiPaddlePlugin::fold_between_walls(y, wall_width) - y).abs() < 1e-3,
+				"{}",
+				y
+			);
 		}
+		// Thicker walls leave less room
+		assert!((AiPaddlePlugin::fold_between_walls(MAX_Y, 40.) - (MAX_Y - 60.)).abs() < 1e-3);
 	}
 }
@@ -1,227 +1,221 @@
-use bevy::{
-	prelude::*,
-	sprite::collide_aabb::{collide, Collision},
-};
-use bevy_turborand::*;
-use std::{f32::consts::PI, ops::DerefMut};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-	collider::Collider, paddle::Paddle, reset::Reset, score::Score, wall::Wall, GameSet, GameState, PongPlugin,
+	collider::Aabb,
+	config::GameConfig,
+	player::Player,
+	power_up::PowerUpPhysics,
+	simulation::{PaddleState, Simulation},
+	GameSet,
 };
 
-const MAX_BOUNCE_ANGLE: f32 = 5.0 * PI / 12.0;
-const BALL_SPEED: f32 = 400.0;
-
-#[derive(Component, Debug)]
+/// Mirrors the [`BallState`] for everything outside the simulation.
+#[derive(Component, Debug, Default)]
 pub struct Ball {
-	direction: Vec2,
-	speed: f32,
+	velocity: Vec2,
+}
+
+impl Ball {
+	pub fn velocity(&self) -> Vec2 {
+		self.velocity
+	}
 }
 
+/// Sent whenever the ball hits something, with the point where it touched.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum CollisionEvent {
-	Paddle,
-	Wall,
-	Goal,
+	Paddle { contact: Vec2 },
+	Wall { contact: Vec2 },
+	Goal { scorer: Player, contact: Vec2 },
+}
+
+/// How the ball speeds up during a rally.
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct BallPhysics {
+	/// Factor the ball's speed is multiplied by on every paddle hit.
+	pub speed_up: f32,
+	/// The ball never gets faster than this.
+	pub max_speed: f32,
+	/// Lets paddles put spin on the ball, `None` for the classic behaviour.
+	pub spin: Option<SpinPhysics>,
+	/// Lets power-ups appear on the field, `None` for none.
+	pub power_ups: Option<PowerUpPhysics>,
+}
+
+impl Default for BallPhysics {
+	fn default() -> Self {
+		Self {
+			speed_up: 1.05,
+			max_speed: GameConfig::default().ball_speed * 2.,
+			spin: None,
+			power_ups: None,
+		}
+	}
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub struct WaitAfterGoalTimer(Timer);
+/// How a moving paddle affects the ball it hits.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpinPhysics {
+	/// Radians per second the ball curves by when hit by a paddle moving at full speed.
+	/// The ball curves towards the direction the paddle was moving in.
+	pub curve: f32,
+	/// Share of the spin lost per second.
+	pub decay: f32,
+	/// Share of the paddle's velocity added to the ball's velocity on a hit.
+	pub momentum_transfer: f32,
+}
+
+impl Default for SpinPhysics {
+	fn default() -> Self {
+		Self {
+			curve: 1.5,
+			decay: 0.5,
+			momentum_transfer: 0.3,
+		}
+	}
+}
+
+/// Paddle hits since the ball was last served.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Resource, Serialize)]
+pub struct Rally {
+	pub hits: usize,
+	/// The longest rally of the current match.
+	pub longest: usize,
+}
+
+/// The ball as seen by the [`Simulation`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BallState {
+	pub position: Vec2,
+	pub direction: Vec2,
+	pub speed: f32,
+	/// Radians per second the ball curves by, see [`SpinPhysics`].
+	pub spin: f32,
+}
+
+impl BallState {
+	/// A ball in the centre of the field, moving in `direction` at `speed`.
+	pub fn new(direction: Vec2, speed: f32) -> Self {
+		Self {
+			position: Vec2::ZERO,
+			direction: direction.normalize(),
+			speed,
+			spin: 0.,
+		}
+	}
 
-impl Ball {
 	pub fn velocity(&self) -> Vec2 {
 		self.direction.normalize() * self.speed
 	}
-}
 
-#[derive(Default)]
-pub struct LastCollision(pub Option<Collision>);
+	/// The ball's box, `size` pixels wide and high.
+	pub fn aabb(&self, size: f32) -> Aabb {
+		Aabb::new(self.position, Vec2::splat(size))
+	}
+
+	/// Stops the ball until it is [launched](Self::launch) again.
+	pub fn hold(&mut self) {
+		self.speed = 0.;
+	}
+
+	pub fn launch(&mut self, direction: Vec2, speed: f32) {
+		self.direction = direction.normalize();
+		self.speed = speed;
+		self.spin = 0.;
+	}
+
+	/// Adds a share of the paddle's momentum and spin to a ball that just bounced off it, never sending it off
+	/// steeper than `max_angle` radians.
+	pub fn apply_spin(&mut self, paddle: &PaddleState, spin: &SpinPhysics, max_angle: f32) {
+		let velocity = self.velocity() + Vec2::new(0., paddle.velocity) * spin.momentum_transfer;
+		self.direction = Self::limit_angle(velocity.normalize_or_zero(), self.direction, max_angle);
+		self.spin = (paddle.velocity / paddle.speed).clamp(-1., 1.) * spin.curve;
+	}
+
+	/// Curves the ball's path according to its spin, the spin stops once the path gets steeper than `max_angle`.
+	pub fn curve(&mut self, spin: &SpinPhysics, delta_seconds: f32, max_angle: f32) {
+		if self.spin == 0. {
+			return;
+		}
+
+		// Rotating counterclockwise turns a ball moving to the right upwards, but one moving to the left downwards
+		let angle = self.spin * self.direction.x.signum() * delta_seconds;
+		let direction = Vec2::from_angle(angle).rotate(self.direction);
+		if direction.y.atan2(direction.x.abs()).abs() > max_angle {
+			self.spin = 0.;
+			return;
+		}
+
+		self.direction = direction;
+		self.spin *= (1. - spin.decay * delta_seconds).max(0.);
+	}
+
+	/// Returns `direction`, unless it is steeper than `max_angle` or turns the ball around,
+	/// in which case `fallback` is returned.
+	fn limit_angle(direction: Vec2, fallback: Vec2, max_angle: f32) -> Vec2 {
+		let turned_around = direction.x.signum() != fallback.x.signum();
+		if direction == Vec2::ZERO || turned_around || direction.y.atan2(direction.x.abs()).abs() > max_angle {
+			fallback
+		} else {
+			direction
+		}
+	}
+}
 
 pub struct BallPlugin;
 impl Plugin for BallPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_startup_system(Self::setup)
 			.add_event::<CollisionEvent>()
-			.add_systems(
-				(
-					Self::handle_reset.in_set(GameSet::Reset),
-					Self::pause_after_goal,
-					Self::update_position
-						.in_set(GameSet::Movement)
-						.run_if(PongPlugin::is_playing),
-					Self::check_collision
-						.in_set(GameSet::CollisionDetection)
-						.run_if(PongPlugin::is_playing),
-				)
-					.chain()
-					.in_schedule(CoreSchedule::FixedUpdate),
-			);
+			.init_resource::<BallPhysics>()
+			.init_resource::<Rally>()
+			.add_system(Self::sync.in_set(GameSet::Sync).in_schedule(CoreSchedule::FixedUpdate));
 	}
 }
 
 impl BallPlugin {
-	pub fn setup(mut commands: Commands, mut rng: ResMut<GlobalRng>) {
-		let direction = Vec2::new(if rng.bool() { 1. } else { -1. }, if rng.bool() { 0.5 } else { -0.5 });
+	pub fn setup(mut commands: Commands, config: Res<GameConfig>) {
 		commands.spawn((
 			Name::new("Ball"),
-			Ball {
-				speed: BALL_SPEED,
-				direction,
-			},
+			Ball::default(),
 			SpriteBundle {
 				sprite: Sprite {
-					custom_size: Some(Vec2::new(Wall::WIDTH, Wall::WIDTH)),
+					custom_size: Some(Vec2::splat(config.wall_width)),
 					..default()
 				},
 				..default()
 			},
 		));
-
-		commands.spawn(WaitAfterGoalTimer(Timer::from_seconds(2.0, TimerMode::Once)));
 	}
 
-	pub fn handle_reset(
-		mut query: Query<(&mut Transform, &mut Ball)>,
-		mut reset_reader: EventReader<Reset>,
-		mut rng: ResMut<GlobalRng>,
+	/// Moves the ball to where the [`Simulation`] put it, resizing it when the config changed.
+	pub fn sync(
+		simulation: Res<Simulation>,
+		mut query: Query<(&mut Ball, &mut Transform, &mut Sprite)>,
+		mut rally: ResMut<Rally>,
 	) {
-		if reset_reader.iter().last().is_none() {
-			return;
-		}
-
-		let speed = BALL_SPEED;
-
-		for (mut transform, mut ball) in query.iter_mut() {
-			ball.direction =
-				Vec2::new(if rng.bool() { 1. } else { -1. }, if rng.bool() { 0.5 } else { -0.5 }).normalize();
-			ball.speed = speed;
-			transform.translation = Vec3::default();
-		}
-	}
-
-	pub fn check_collision(
-		collider_query: Query<(&Transform, &Sprite, Option<&Wall>, Option<&Paddle>), With<Collider>>,
-		mut ball_query: Query<(&mut Ball, &Transform, &Sprite)>,
-		mut collision_events: EventWriter<CollisionEvent>,
-		mut last_collision: Local<LastCollision>,
-		mut next_state: ResMut<NextState<GameState>>,
-		mut reset_writer: EventWriter<Reset>,
-		mut score: ResMut<Score>,
-		state: Res<State<GameState>>,
-	) {
-		let (mut ball, ball_transform, ball_sprite) = ball_query.single_mut();
-
-		for (collider_transform, collider_sprite, wall, paddle) in &collider_query {
-			if ball_sprite.custom_size.is_some() && collider_sprite.custom_size.is_some() {
-				let collision = collide(
-					ball_transform.translation,
-					ball_sprite.custom_size.unwrap(),
-					collider_transform.translation,
-					collider_sprite.custom_size.unwrap(),
-				);
-
-				if collision.is_none() {
-					continue;
-				}
-
-				if last_collision.0 == collision {
-					debug!("Duplicate Collision: {:?}", collision);
-					continue;
-				} else {
-					debug!("Normal Collision: {:?}", collision);
-					last_collision.0 = match collision {
-						Some(Collision::Top) => Some(Collision::Top),
-						Some(Collision::Bottom) => Some(Collision::Bottom),
-						Some(Collision::Left) => Some(Collision::Left),
-						Some(Collision::Right) => Some(Collision::Right),
-						Some(Collision::Inside) => Some(Collision::Inside),
-						None => None,
-					};
-				}
-
-				if paddle.is_some() {
-					debug!("Paddle collision: {:?}", collision);
-
-					let ball_position = ball_transform.translation;
-					let paddle_position = collider_transform.translation;
-
-					let ball_angle = Self::calculate_bounce_angle(&paddle_position, &ball_position);
-					debug!("Ball angle: {:?}", ball_angle);
-
-					ball.direction = match collision {
-						Some(Collision::Right) => Vec2::new(ball_angle.x, -ball_angle.y),
-						Some(Collision::Left) => Vec2::new(-ball_angle.x, -ball_angle.y),
-						_ => Vec2::new(-ball.direction.x, -ball.direction.y),
-					};
-
-					collision_events.send(CollisionEvent::Paddle);
-				} else {
-					let (reflect_x, reflect_y) = match collision.unwrap() {
-						Collision::Top => (false, ball.direction.y < 0.),
-						Collision::Right => (ball.direction.x < 0., false),
-						Collision::Left => (ball.direction.x > 0., false),
-						Collision::Bottom => (false, ball.direction.y > 0.),
-						Collision::Inside => (false, false),
-					};
-
-					if reflect_x {
-						ball.direction.x = -ball.direction.x;
-					}
-
-					if reflect_y {
-						ball.direction.y = -ball.direction.y;
-					}
-
-					if let Some(wall) = wall {
-						match wall {
-							Wall::Top | Wall::Bottom => {
-								collision_events.send(CollisionEvent::Wall);
-							}
-							Wall::Right | Wall::Left => {
-								if *wall == Wall::Right {
-									score.deref_mut().left += 1;
-								} else if *wall == Wall::Left {
-									score.deref_mut().right += 1;
-								}
-								collision_events.send(CollisionEvent::Goal);
-								ball.speed = 0.;
-								reset_writer.send(Reset::Soft);
-								if state.0 == GameState::Playing {
-									next_state.set(GameState::WaitBeforeRound);
-								}
-							}
-						}
-					}
-				}
+		let state = &simulation.state;
+		let size = Vec2::splat(simulation.settings.config.wall_width);
+
+		for (mut ball, mut transform, mut sprite) in query.iter_mut() {
+			ball.velocity = state.ball.velocity();
+			transform.translation = state.ball.position.extend(0.);
+			if sprite.custom_size != Some(size) {
+				sprite.custom_size = Some(size);
 			}
 		}
-	}
-
-	fn pause_after_goal(
-		mut next_state: ResMut<NextState<GameState>>,
-		mut query: Query<&mut WaitAfterGoalTimer>,
-		state: Res<State<GameState>>,
-		time: Res<Time>,
-	) {
-		if state.0 == GameState::WaitBeforeRound {
-			for mut timer in &mut query {
-				if timer.tick(time.delta()).just_finished() {
-					timer.reset();
-					next_state.set(GameState::Playing);
-				}
-			}
-		}
-	}
 
-	pub fn update_position(time: Res<Time>, mut query: Query<(&Ball, &mut Transform)>) {
-		for (ball, mut transform) in query.iter_mut() {
-			transform.translation += time.delta_seconds() * ball.velocity().extend(0.);
+		if *rally != state.rally {
+			*rally = state.rally.clone();
 		}
 	}
 
-	pub fn calculate_bounce_angle(paddle_pos: &Vec3, hit_pos: &Vec3) -> Vec2 {
+	pub fn calculate_bounce_angle(paddle_pos: &Vec3, hit_pos: &Vec3, paddle_height: f32, config: &GameConfig) -> Vec2 {
 		let relative_ball_pos = *hit_pos - *paddle_pos;
-		let normalized_relative_ball_pos = relative_ball_pos / Paddle::HEIGHT;
-		let bounce_angle = normalized_relative_ball_pos * MAX_BOUNCE_ANGLE;
+		let normalized_relative_ball_pos = relative_ball_pos / paddle_height;
+		let bounce_angle = normalized_relative_ball_pos * config.max_bounce_angle_radians();
 		Vec2::new(f32::cos(bounce_angle.x), -f32::sin(bounce_angle.y)).normalize()
 	}
 }
@@ -0,0 +1,57 @@
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+use bevy_pong::{cli, server::MatchServer};
+use clap::Parser;
+
+/// Hosts one match after another for two clients, without a window. Spectators can join any time.
+#[derive(Parser)]
+#[command(name = "pong-server")]
+struct Args {
+	/// Where players and spectators connect over UDP
+	#[arg(long, value_name = "ADDRESS", default_value = "0.0.0.0:7878")]
+	bind: SocketAddr,
+	/// Also accepts WebSocket connections here, needs the `websocket` feature
+	#[arg(long, value_name = "ADDRESS")]
+	websocket: Option<SocketAddr>,
+	/// Seeds all matches [default: random]
+	#[arg(long)]
+	seed: Option<u64>,
+	#[command(flatten)]
+	preset: cli::PresetArgs,
+	/// Where replays are saved
+	#[arg(long, value_name = "DIRECTORY", default_value = "replays")]
+	replays: PathBuf,
+	/// Doesn't save replays
+	#[arg(long, conflicts_with = "replays")]
+	no_replays: bool,
+	/// Appends every result to this file as a line of JSON
+	#[arg(long, value_name = "FILE", default_value = "results.jsonl")]
+	results: PathBuf,
+	/// Doesn't write results
+	#[arg(long, conflicts_with = "results")]
+	no_results: bool,
+	/// Pause between matches
+	#[arg(long, value_name = "SECONDS", default_value = "5", value_parser = cli::seconds)]
+	restart_delay: Duration,
+}
+
+impl Args {
+	fn server(self) -> MatchServer {
+		let preset = self.preset.preset();
+		MatchServer {
+			address: self.bind,
+			websocket: self.websocket,
+			seed: self.seed,
+			rules: preset.rules,
+			physics: preset.physics,
+			config: self.preset.config(),
+			replays: (!self.no_replays).then_some(self.replays),
+			results: (!self.no_results).then_some(self.results),
+			restart_delay: self.restart_delay.as_secs_f32(),
+		}
+	}
+}
+
+fn main() {
+	Args::parse().server().app().run();
+}
@@ -0,0 +1,73 @@
+use std::process::ExitCode;
+
+use bevy_pong::{cli, headless::HeadlessMatch, AiDifficulty};
+use clap::Parser;
+
+/// Plays bot-vs-bot matches without a window and prints one JSON report per match and line.
+#[derive(Parser)]
+#[command(name = "pong-sim")]
+struct Args {
+	#[command(flatten)]
+	preset: cli::PresetArgs,
+	/// Seed of the first match, the following ones count up
+	#[arg(long, default_value_t = 0)]
+	seed: u64,
+	/// Number of matches to play
+	#[arg(long, value_name = "COUNT", default_value_t = 1)]
+	matches: u64,
+	/// Bot on the left: easy, normal, hard or perfect
+	#[arg(long, value_name = "DIFFICULTY", default_value = "normal")]
+	left: AiDifficulty,
+	/// Bot on the right
+	#[arg(long, value_name = "DIFFICULTY", default_value = "normal")]
+	right: AiDifficulty,
+	/// Ends the match after this many points, as long as someone is ahead
+	#[arg(long, value_name = "POINTS")]
+	max_rallies: Option<usize>,
+	/// Gives up on a match after this many steps
+	#[arg(long, value_name = "STEPS", default_value_t = HeadlessMatch::default().max_ticks)]
+	max_ticks: u64,
+}
+
+impl Args {
+	fn headless_match(&self) -> HeadlessMatch {
+		let preset = self.preset.preset();
+		let mut rules = preset.rules;
+		if let Some(max_rallies) = self.max_rallies {
+			rules.max_rallies = Some(max_rallies);
+		}
+
+		HeadlessMatch {
+			seed: self.seed,
+			rules,
+			physics: preset.physics,
+			config: self.preset.config(),
+			left: self.left,
+			right: self.right,
+			max_ticks: self.max_ticks,
+		}
+	}
+}
+
+fn main() -> ExitCode {
+	let args = Args::parse();
+	let headless_match = args.headless_match();
+
+	for index in 0..args.matches {
+		let report = HeadlessMatch {
+			seed: headless_match.seed.wrapping_add(index),
+			..headless_match.clone()
+		}
+		.run();
+
+		match serde_json::to_string(&report) {
+			Ok(json) => println!("{}", json),
+			Err(error) => {
+				eprintln!("error: failed to serialize the report: {}", error);
+				return ExitCode::FAILURE;
+			}
+		}
+	}
+
+	ExitCode::SUCCESS
+}
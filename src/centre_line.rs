@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{reset::Reset, wall::Wall, GameSet, WINDOW_HEIGHT};
+use crate::{config::GameConfig, reset::Reset, simulation::Simulation, GameSet, WINDOW_HEIGHT};
 
 #[derive(Component)]
 pub struct CentreLine;
@@ -10,29 +10,61 @@ pub struct CentreLinePlugin;
 impl Plugin for CentreLinePlugin {
 	fn build(&self, app: &mut App) {
 		app.add_startup_system(Self::setup)
-			.add_system(Self::handle_reset.in_set(GameSet::Reset));
+			.add_system(Self::handle_reset.in_set(GameSet::Reset))
+			.add_system(Self::resize);
 	}
 }
 
 impl CentreLinePlugin {
-	pub fn setup(mut commands: Commands) {
+	pub fn setup(mut commands: Commands, config: Res<GameConfig>) {
 		commands
 			.spawn((CentreLine, Name::new("Centre Line"), SpatialBundle::default()))
-			.with_children(|commands| {
-				for i in 0..(WINDOW_HEIGHT / Wall::WIDTH) as i32 {
-					commands.spawn((SpriteBundle {
-						transform: Transform {
-							translation: Vec3::new(0.0, i as f32 * Wall::WIDTH * 2.0 - WINDOW_HEIGHT / 2.0, 0.0),
-							..default()
-						},
-						sprite: Sprite {
-							custom_size: Some(Vec2::new(Wall::WIDTH, Wall::WIDTH)),
-							..default()
-						},
-						..default()
-					},));
-				}
-			});
+			.with_children(|commands| Self::spawn_dashes(commands, config.wall_width));
+	}
+
+	/// Dashes as big as the ball, with a gap as high as a dash between each of them.
+	fn spawn_dashes(commands: &mut ChildBuilder, width: f32) {
+		for i in 0..(WINDOW_HEIGHT / width) as i32 {
+			commands.spawn((SpriteBundle {
+				transform: Transform {
+					translation: Vec3::new(0.0, i as f32 * width * 2.0 - WINDOW_HEIGHT / 2.0, 0.0),
+					..default()
+				},
+				sprite: Sprite {
+					custom_size: Some(Vec2::new(width, width)),
+					..default()
+				},
+				..default()
+			},));
+		}
+	}
+
+	/// Redraws the dashes when the config changes the wall width.
+	fn resize(
+		mut commands: Commands,
+		simulation: Res<Simulation>,
+		query: Query<(Entity, &Children), With<CentreLine>>,
+		sprite_query: Query<&Sprite>,
+	) {
+		if !simulation.is_changed() {
+			return;
+		}
+
+		let width = simulation.settings.config.wall_width;
+		for (entity, children) in query.iter() {
+			let size = children
+				.first()
+				.and_then(|child| sprite_query.get(*child).ok())
+				.and_then(|sprite| sprite.custom_size);
+			if size == Some(Vec2::splat(width)) {
+				continue;
+			}
+
+			commands
+				.entity(entity)
+				.despawn_descendants()
+				.with_children(|commands| Self::spawn_dashes(commands, width));
+		}
 	}
 
 	pub fn handle_reset(mut query: Query<&mut Transform, With<CentreLine>>, mut reset_reader: EventReader<Reset>) {
@@ -0,0 +1,108 @@
+//! Command-line options shared by the binaries, parsed with [`clap`].
+
+use std::{path::PathBuf, time::Duration};
+
+use clap::Args;
+
+use crate::{
+	config::GameConfig,
+	preset::{Preset, PresetConfig, Presets},
+	replay::Replay,
+	rules::ServeRule,
+	PowerUpPhysics, SpinPhysics,
+};
+
+/// The preset to play and the rules to change about it.
+#[derive(Args, Clone, Debug)]
+pub struct PresetArgs {
+	/// classic, arcade, tournament, lunchtime, a saved preset or a .preset.json file, the options below change it
+	#[arg(long, value_name = "NAME", default_value = "classic", value_parser = preset)]
+	pub preset: Preset,
+	/// Points needed to win [default: the preset's]
+	#[arg(long, value_name = "POINTS", value_parser = clap::value_parser!(u64).range(1..))]
+	pub target_score: Option<u64>,
+	/// Lead needed to win [default: the preset's]
+	#[arg(long, value_name = "POINTS", value_parser = clap::value_parser!(u64).range(1..))]
+	pub win_by: Option<u64>,
+	/// Plays a timed match, which only ends on points with --target-score [default: the preset's]
+	#[arg(long, value_name = "SECONDS", value_parser = time_limit)]
+	pub time_limit: Option<Duration>,
+	/// random, loser, winner or alternate:<points> [default: the preset's]
+	#[arg(long, value_name = "RULE")]
+	pub serve: Option<ServeRule>,
+	/// Lets moving paddles put spin on the ball
+	#[arg(long)]
+	pub spin: bool,
+	/// Lets power-ups appear on the field
+	#[arg(long)]
+	pub power_ups: bool,
+}
+
+impl PresetArgs {
+	/// The preset with the options given on top of it.
+	pub fn preset(&self) -> Preset {
+		let mut preset = self.preset.clone();
+		let rules = &mut preset.rules;
+		if let Some(time_limit) = self.time_limit {
+			rules.time_limit = Some(time_limit);
+			// Only the clock ends the match, unless --target-score is given as well
+			rules.target_score = usize::MAX;
+		}
+		if let Some(target_score) = self.target_score {
+			rules.target_score = target_score as usize;
+		}
+		if let Some(win_by) = self.win_by {
+			rules.win_by = win_by as usize;
+		}
+		if let Some(serve) = self.serve {
+			rules.serve = serve;
+		}
+		if self.spin {
+			preset.physics.spin = Some(SpinPhysics::default());
+		}
+		if self.power_ups {
+			preset.physics.power_ups = Some(PowerUpPhysics::default());
+		}
+		preset
+	}
+
+	/// Every preset, with this one selected and changed by the options.
+	pub fn presets(&self) -> Presets {
+		let mut presets = Presets::load(PresetConfig::default().directory.as_deref());
+		let index = presets.add(self.preset());
+		presets.select(index);
+		presets
+	}
+
+	/// The config to play with when there's no config file to tune it.
+	pub fn config(&self) -> GameConfig {
+		self.preset.config(&GameConfig::default())
+	}
+}
+
+/// Finds a shipped or saved preset, see [`Preset::find`].
+pub fn preset(name: &str) -> Result<Preset, String> {
+	Preset::find(name).map_err(|error| error.to_string())
+}
+
+/// A number of seconds, which can't be negative.
+pub fn seconds(value: &str) -> Result<Duration, String> {
+	let seconds: f32 = value.parse().map_err(|_| "not a number of seconds".to_owned())?;
+	Duration::try_from_secs_f32(seconds).map_err(|error| error.to_string())
+}
+
+/// The length of a timed match, which has to be more than 0 seconds so the clock can run out.
+pub fn time_limit(value: &str) -> Result<Duration, String> {
+	let time_limit = seconds(value)?;
+	if time_limit.is_zero() {
+		return Err("a timed match needs more than 0 seconds".to_owned());
+	}
+	Ok(time_limit)
+}
+
+/// The path of a replay that can be played, so a broken one is refused before a window opens.
+pub fn replay(path: &str) -> Result<PathBuf, String> {
+	let path = PathBuf::from(path);
+	Replay::load(&path).map_err(|error| error.to_string())?;
+	Ok(path)
+}
@@ -2,3 +2,156 @@ use bevy::prelude::*;
 
 #[derive(Default, Component)]
 pub struct Collider;
+
+/// An axis-aligned box, given by its centre and half its size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+	pub centre: Vec2,
+	pub half_size: Vec2,
+}
+
+impl Aabb {
+	pub fn new(centre: Vec2, size: Vec2) -> Self {
+		Self {
+			centre,
+			half_size: size / 2.,
+		}
+	}
+
+	/// Whether the boxes overlap, boxes that only touch don't.
+	pub fn overlaps(&self, other: &Aabb) -> bool {
+		let distance = (self.centre - other.centre).abs();
+		let reach = self.half_size + other.half_size;
+		distance.x < reach.x && distance.y < reach.y
+	}
+}
+
+/// Where a moving [`Aabb`] first touches another one, see [`sweep`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+	/// Fraction of the motion covered before the boxes touch, from `0.` to `1.`.
+	pub time: f32,
+	/// Points away from the surface that was hit.
+	pub normal: Vec2,
+	/// The point on the surface where the boxes touch.
+	pub contact: Vec2,
+}
+
+/// Sweeps `moving` along `motion` and returns where it first hits `target`, if it does.
+///
+/// Boxes that already overlap, merely slide along each other, or move apart don't hit.
+pub fn sweep(moving: &Aabb, motion: Vec2, target: &Aabb) -> Option<Hit> {
+	// Grow the target by the moving box, so only the moving box's centre has to be traced
+	let reach = target.half_size + moving.half_size;
+	let min = target.centre - reach;
+	let max = target.centre + reach;
+
+	let mut entry = f32::NEG_INFINITY;
+	let mut exit = f32::INFINITY;
+	let mut normal = Vec2::ZERO;
+
+	for axis in 0..2 {
+		let origin = moving.centre[axis];
+		let delta = motion[axis];
+
+		if delta == 0. {
+			if origin <= min[axis] || origin >= max[axis] {
+				return None;
+			}
+			continue;
+		}
+
+		let (near, far) = {
+			let to_min = (min[axis] - origin) / delta;
+			let to_max = (max[axis] - origin) / delta;
+			(to_min.min(to_max), to_min.max(to_max))
+		};
+
+		if near > entry {
+			entry = near;
+			normal = Vec2::ZERO;
+			normal[axis] = -delta.signum();
+		}
+		exit = exit.min(far);
+	}
+
+	if entry > exit || !(0. ..=1.).contains(&entry) {
+		return None;
+	}
+
+	let centre = moving.centre + motion * entry;
+	Some(Hit {
+		time: entry,
+		normal,
+		contact: centre - normal * moving.half_size,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A ball as wide as the default walls.
+	fn ball(x: f32, y: f32) -> Aabb {
+		Aabb::new(Vec2::new(x, y), Vec2::splat(20.))
+	}
+
+	fn paddle() -> Aabb {
+		Aabb::new(Vec2::new(600., 0.), Vec2::new(20., 144.))
+	}
+
+	#[test]
+	fn fast_balls_hit_instead_of_tunnelling() {
+		// Moved all at once, the ball would jump from one side of the paddle to the other
+		let hit = sweep(&ball(0., 0.), Vec2::new(2000., 0.), &paddle()).expect("the ball should hit the paddle");
+
+		assert_eq!(hit.time, 580. / 2000.);
+		assert_eq!(hit.normal, Vec2::new(-1., 0.));
+		assert!(hit.contact.abs_diff_eq(Vec2::new(590., 0.), 1e-3), "{:?}", hit.contact);
+	}
+
+	#[test]
+	fn corners_are_hit() {
+		let target = Aabb::new(Vec2::new(100., 100.), Vec2::splat(20.));
+		let hit = sweep(&ball(0., 0.), Vec2::new(100., 100.), &target).expect("the ball should hit the corner");
+
+		assert_eq!(hit.time, 0.8);
+		assert!(
+			hit.normal == Vec2::new(-1., 0.) || hit.normal == Vec2::new(0., -1.),
+			"{:?}",
+			hit.normal
+		);
+	}
+
+	#[test]
+	fn grazing_balls_only_hit_when_they_overlap() {
+		// The ball's bottom slides along the paddle's top
+		let sliding = ball(0., 72. + 10.);
+		assert_eq!(sweep(&sliding, Vec2::new(2000., 0.), &paddle()), None);
+
+		let clipping = ball(0., 72. + 9.);
+		let hit = sweep(&clipping, Vec2::new(2000., 0.), &paddle()).expect("the ball should clip the paddle");
+		assert_eq!(hit.normal, Vec2::new(-1., 0.));
+	}
+
+	#[test]
+	fn overlapping_boxes_dont_hit() {
+		assert_eq!(sweep(&ball(595., 0.), Vec2::new(100., 0.), &paddle()), None);
+		assert_eq!(sweep(&ball(595., 0.), Vec2::new(-100., 0.), &paddle()), None);
+	}
+
+	#[test]
+	fn boxes_out_of_reach_dont_hit() {
+		// Moving away, and not getting there within the motion
+		assert_eq!(sweep(&ball(0., 0.), Vec2::new(-2000., 0.), &paddle()), None);
+		assert_eq!(sweep(&ball(0., 0.), Vec2::new(500., 0.), &paddle()), None);
+	}
+
+	#[test]
+	fn touching_boxes_dont_overlap() {
+		assert!(ball(595., 0.).overlaps(&paddle()));
+		assert!(ball(0., 72. + 9.).overlaps(&Aabb::new(Vec2::new(0., 0.), Vec2::new(20., 144.))));
+		assert!(!ball(580., 0.).overlaps(&paddle()));
+		assert!(!ball(0., 0.).overlaps(&paddle()));
+	}
+}
@@ -0,0 +1,277 @@
+//! The numbers that make the game feel the way it does, see [`GameConfig`].
+
+use std::{f32::consts::PI, fmt};
+
+use bevy::{
+	asset::{AssetLoader, LoadContext, LoadedAsset},
+	prelude::*,
+	reflect::TypeUuid,
+	utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	net::NetMode, preset::PresetPlugin, replay::Recorder, simulation::Simulation, GameState, TIME_STEP, WINDOW_HEIGHT,
+	WINDOW_WIDTH,
+};
+
+/// Where the [`GameConfig`] asset is loaded from, relative to the assets directory.
+pub const GAME_CONFIG_PATH: &str = "game.config.json";
+
+/// Sizes, speeds and timings of the game. Missing fields keep their defaults.
+///
+/// Follows the [`TunedConfig`] unless the selected [`crate::preset::Preset`] brings its own. A match in progress
+/// picks up changes right away when playing offline, otherwise with the next match.
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize, TypeUuid)]
+#[uuid = "7c0e9a3e-5d0b-4b8e-9f3c-2a6b1d4e8f17"]
+#[serde(default)]
+pub struct GameConfig {
+	/// Pixels per second the ball is served with.
+	pub ball_speed: f32,
+	/// Degrees the ball leaves a paddle at when it hits the paddle's very edge.
+	pub max_bounce_angle: f32,
+	pub paddle_height: f32,
+	/// Pixels per second a paddle moves at full speed.
+	pub paddle_speed: f32,
+	/// Room between a paddle and the wall behind it.
+	pub paddle_margin: f32,
+	/// Thickness of the walls, which is also the width of the paddles and the size of the ball.
+	pub wall_width: f32,
+	/// Seconds between a goal and the next serve.
+	pub wait_after_goal: f32,
+	/// Seconds simulated by every step.
+	pub time_step: f32,
+}
+
+impl Default for GameConfig {
+	fn default() -> Self {
+		Self {
+			ball_speed: 400.,
+			max_bounce_angle: 75.,
+			paddle_height: WINDOW_HEIGHT * 0.2,
+			paddle_speed: WINDOW_HEIGHT / 2.5,
+			paddle_margin: 40.,
+			wall_width: 20.,
+			wait_after_goal: 2.,
+			time_step: TIME_STEP,
+		}
+	}
+}
+
+/// Why a [`GameConfig`] was refused.
+#[derive(Debug)]
+pub enum ConfigError {
+	Json(serde_json::Error),
+	/// A field is out of range, with a description of the range.
+	OutOfRange {
+		field: &'static str,
+		value: f32,
+		expected: String,
+	},
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ConfigError::Json(error) => write!(formatter, "the game config is damaged: {}", error),
+			ConfigError::OutOfRange { field, value, expected } => {
+				write!(formatter, "`{}` is {}, but it must be {}", field, value, expected)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<serde_json::Error> for ConfigError {
+	fn from(error: serde_json::Error) -> Self {
+		ConfigError::Json(error)
+	}
+}
+
+impl GameConfig {
+	/// [`Self::max_bounce_angle`] in radians.
+	pub fn max_bounce_angle_radians(&self) -> f32 {
+		// Not `to_radians`, which rounds differently and would make older replays play out differently
+		self.max_bounce_angle * PI / 180.
+	}
+
+	/// Reads a config and checks that the game can be played with it.
+	pub fn from_json(json: &[u8]) -> Result<Self, ConfigError> {
+		let config: Self = serde_json::from_slice(json)?;
+		config.validate()?;
+		Ok(config)
+	}
+
+	pub fn validate(&self) -> Result<(), ConfigError> {
+		let max_wall_width = WINDOW_HEIGHT / 4.;
+		Self::check(
+			"wall_width",
+			self.wall_width,
+			self.wall_width > 0. && self.wall_width < max_wall_width,
+			format!("more than 0 and less than {}", max_wall_width),
+		)?;
+		// The paddles have to fit between the walls
+		let field_height = WINDOW_HEIGHT - 2. * self.wall_width;
+		Self::check(
+			"paddle_height",
+			self.paddle_height,
+			self.paddle_height > 0. && self.paddle_height <= field_height,
+			format!(
+				"more than 0 and at most {} with walls {} wide",
+				field_height, self.wall_width
+			),
+		)?;
+		let max_margin = WINDOW_WIDTH / 4.;
+		Self::check(
+			"paddle_margin",
+			self.paddle_margin,
+			self.paddle_margin >= 0. && self.paddle_margin < max_margin,
+			format!("at least 0 and less than {}", max_margin),
+		)?;
+		Self::check("paddle_speed", self.paddle_speed, self.paddle_speed > 0., "more than 0")?;
+		Self::check("ball_speed", self.ball_speed, self.ball_speed > 0., "more than 0")?;
+		Self::check(
+			"max_bounce_angle",
+			self.max_bounce_angle,
+			self.max_bounce_angle > 0. && self.max_bounce_angle < 90.,
+			"more than 0 and less than 90 degrees",
+		)?;
+		Self::check(
+			"wait_after_goal",
+			self.wait_after_goal,
+			self.wait_after_goal >= 0.,
+			"at least 0 seconds",
+		)?;
+		// Longer steps make the game stutter, shorter ones can't be kept up with
+		Self::check(
+			"time_step",
+			self.time_step,
+			(0.001..=0.1).contains(&self.time_step),
+			"between 0.001 and 0.1 seconds",
+		)
+	}
+
+	pub(crate) fn check(
+		field: &'static str,
+		value: f32,
+		valid: bool,
+		expected: impl Into<String>,
+	) -> Result<(), ConfigError> {
+		if value.is_finite() && valid {
+			Ok(())
+		} else {
+			Err(ConfigError::OutOfRange {
+				field,
+				value,
+				expected: expected.into(),
+			})
+		}
+	}
+}
+
+#[derive(Default)]
+struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+	fn load<'a>(
+		&'a self,
+		bytes: &'a [u8],
+		load_context: &'a mut LoadContext,
+	) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+		Box::pin(async move {
+			let config = GameConfig::from_json(bytes)?;
+			load_context.set_default_asset(LoadedAsset::new(config));
+			Ok(())
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["config.json"]
+	}
+}
+
+/// The config as loaded from [`GAME_CONFIG_PATH`], reloaded whenever the file changes.
+#[derive(Debug, Default, Resource)]
+pub struct TunedConfig(pub GameConfig);
+
+/// Keeps the handle so the asset stays loaded and gets reloaded.
+#[derive(Default, Resource)]
+struct GameConfigHandle(Handle<GameConfig>);
+
+/// Provides the default [`GameConfig`] and applies every change to it.
+pub struct ConfigPlugin;
+impl Plugin for ConfigPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<GameConfig>()
+			.init_resource::<TunedConfig>()
+			.add_system(Self::apply.after(PresetPlugin::apply));
+	}
+}
+
+impl ConfigPlugin {
+	/// A config inserted before the app ran was already used to set up the [`Simulation`].
+	fn apply(
+		config: Res<GameConfig>,
+		mut simulation: ResMut<Simulation>,
+		mut recorder: ResMut<Recorder>,
+		mode: Res<NetMode>,
+		state: Res<State<GameState>>,
+	) {
+		if !config.is_changed() || config.is_added() {
+			return;
+		}
+
+		// Both sides of a network match have to play by the same config, and a replay by the one it was
+		// recorded with
+		let live = *mode == NetMode::Offline && state.0 != GameState::Replay;
+		if live && simulation.settings.config != *config {
+			info!("Applying the game config");
+			simulation.settings.config = config.clone();
+			if recorder.recording.take().is_some() {
+				info!("The config changed during the match, it won't be saved as a replay");
+			}
+		}
+	}
+}
+
+/// Loads the [`TunedConfig`] asset and reloads it whenever it changes.
+pub struct ConfigAssetPlugin;
+impl Plugin for ConfigAssetPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_asset::<GameConfig>()
+			.init_asset_loader::<GameConfigLoader>()
+			.init_resource::<GameConfigHandle>()
+			.add_startup_system(Self::setup)
+			.add_system(Self::load.before(PresetPlugin::apply));
+	}
+}
+
+impl ConfigAssetPlugin {
+	fn setup(mut handle: ResMut<GameConfigHandle>, asset_server: Res<AssetServer>) {
+		handle.0 = asset_server.load(GAME_CONFIG_PATH);
+	}
+
+	/// Invalid files never get here, the asset server logs why they were refused and the last config stays.
+	fn load(
+		mut asset_events: EventReader<AssetEvent<GameConfig>>,
+		assets: Res<Assets<GameConfig>>,
+		handle: Res<GameConfigHandle>,
+		mut tuned: ResMut<TunedConfig>,
+	) {
+		for event in asset_events.iter() {
+			let (AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }) = event else {
+				continue;
+			};
+			if *changed != handle.0 {
+				continue;
+			}
+			let Some(loaded) = assets.get(changed) else {
+				continue;
+			};
+			if *loaded != tuned.0 {
+				tuned.0 = loaded.clone();
+			}
+		}
+	}
+}
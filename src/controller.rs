@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::{
+	ai_paddle::{AiDifficulty, AiPaddle},
+	paddle::Paddle,
+	player::Player,
+	GameState,
+};
+
+/// Decides who drives a paddle.
+#[derive(Clone, Copy, Component, Debug, PartialEq)]
+pub enum Controller {
+	/// A player on this machine, using the keyboard, the gamepad assigned to their side, or the mouse and touches on
+	/// their half of the screen.
+	Local,
+	Ai(AiDifficulty),
+	/// A player on another machine, see [`crate::net`].
+	Remote,
+	/// Follows the [`crate::script::InputScript`].
+	Script,
+}
+
+/// The [`Controller`] of each side. Changing it reassigns the paddles.
+#[derive(Debug, Resource)]
+pub struct Controllers {
+	pub left: Controller,
+	pub right: Controller,
+}
+
+impl Default for Controllers {
+	fn default() -> Self {
+		Self::human_vs_bot(AiDifficulty::default())
+	}
+}
+
+impl Controllers {
+	pub fn human_vs_human() -> Self {
+		Self {
+			left: Controller::Local,
+			right: Controller::Local,
+		}
+	}
+
+	pub fn human_vs_bot(difficulty: AiDifficulty) -> Self {
+		Self {
+			left: Controller::Local,
+			right: Controller::Ai(difficulty),
+		}
+	}
+
+	/// Who plays the silent match behind the menu.
+	pub fn attract_mode() -> Self {
+		Self::bot_vs_bot(AiDifficulty::Hard)
+	}
+
+	pub fn bot_vs_bot(difficulty: AiDifficulty) -> Self {
+		Self {
+			left: Controller::Ai(difficulty),
+			right: Controller::Ai(difficulty),
+		}
+	}
+
+	pub fn get(&self, player: Player) -> Controller {
+		match player {
+			Player::Left => self.left,
+			Player::Right => self.right,
+		}
+	}
+
+	pub fn set(&mut self, player: Player, controller: Controller) {
+		match player {
+			Player::Left => self.left = controller,
+			Player::Right => self.right = controller,
+		}
+	}
+}
+
+pub struct ControllerPlugin;
+impl Plugin for ControllerPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Controllers>()
+			.add_system(Self::assign_controllers.in_base_set(CoreSet::PostUpdate));
+	}
+}
+
+impl ControllerPlugin {
+	/// Keeps each paddle's [`Controller`] in sync with [`Controllers`], or with
+	/// [`Controllers::attract_mode`] while the menu is shown.
+	///
+	/// Runs after everything that changes the state, and already goes by the upcoming state, so the first step of
+	/// a match is played by the right controllers.
+	fn assign_controllers(
+		mut commands: Commands,
+		controllers: Res<Controllers>,
+		state: Res<State<GameState>>,
+		next_state: Res<NextState<GameState>>,
+		query: Query<(Entity, &Player, Option<&Controller>), With<Paddle>>,
+		added: Query<(), Added<Paddle>>,
+	) {
+		if !controllers.is_changed() && !state.is_changed() && !next_state.is_changed() && added.is_empty() {
+			return;
+		}
+
+		let attract_mode = Controllers::attract_mode();
+		let controllers = if next_state.0.unwrap_or(state.0) == GameState::Menu {
+			&attract_mode
+		} else {
+			controllers.as_ref()
+		};
+
+		for (entity, &player, current) in query.iter() {
+			let controller = controllers.get(player);
+			if current == Some(&controller) {
+				continue;
+			}
+
+			debug!("Assigning {:?} to {:?}", controller, player);
+			let mut entity_commands = commands.entity(entity);
+			entity_commands.insert(controller);
+			match controller {
+				Controller::Ai(difficulty) => {
+					entity_commands.insert(AiPaddle::new(difficulty));
+				}
+				_ => {
+					entity_commands.remove::<AiPaddle>();
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,230 @@
+//! Which key does what, see [`KeyBindings`].
+
+use std::{
+	fmt,
+	path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	files::{self, FileError},
+	player::Player,
+};
+
+mod screen;
+
+pub use screen::ControlsScreenPlugin;
+
+/// Something a key can be bound to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+	Up(Player),
+	Down(Player),
+	Serve(Player),
+	Pause,
+	Reset,
+	/// Closes the game.
+	Quit,
+}
+
+impl Action {
+	pub const ALL: [Action; 9] = [
+		Action::Up(Player::Left),
+		Action::Down(Player::Left),
+		Action::Serve(Player::Left),
+		Action::Up(Player::Right),
+		Action::Down(Player::Right),
+		Action::Serve(Player::Right),
+		Action::Pause,
+		Action::Reset,
+		Action::Quit,
+	];
+}
+
+impl fmt::Display for Action {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Action::Up(player) => write!(formatter, "{:?} up", player),
+			Action::Down(player) => write!(formatter, "{:?} down", player),
+			Action::Serve(player) => write!(formatter, "{:?} serve", player),
+			Action::Pause => write!(formatter, "Pause"),
+			Action::Reset => write!(formatter, "Reset"),
+			Action::Quit => write!(formatter, "Quit"),
+		}
+	}
+}
+
+/// The keys of one side's paddle.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PaddleKeys {
+	pub up: KeyCode,
+	pub down: KeyCode,
+	pub serve: KeyCode,
+}
+
+/// The key bound to every [`Action`], no key does two things.
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct KeyBindings {
+	pub left: PaddleKeys,
+	pub right: PaddleKeys,
+	pub pause: KeyCode,
+	pub reset: KeyCode,
+	pub quit: KeyCode,
+}
+
+impl Default for KeyBindings {
+	fn default() -> Self {
+		Self {
+			left: PaddleKeys {
+				up: KeyCode::W,
+				down: KeyCode::S,
+				serve: KeyCode::D,
+			},
+			right: PaddleKeys {
+				up: KeyCode::Up,
+				down: KeyCode::Down,
+				serve: KeyCode::Left,
+			},
+			pause: KeyCode::P,
+			reset: KeyCode::R,
+			quit: KeyCode::Escape,
+		}
+	}
+}
+
+/// Why [`KeyBindings`] couldn't be loaded or saved.
+#[derive(Debug)]
+pub enum ControlsError {
+	File(FileError),
+	/// Two actions share a key.
+	Conflict(Action, Action),
+}
+
+impl fmt::Display for ControlsError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ControlsError::File(error) => error.describe(formatter, "the key bindings"),
+			ControlsError::Conflict(first, second) => {
+				write!(formatter, "{} and {} are bound to the same key", first, second)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ControlsError {}
+
+impl From<FileError> for ControlsError {
+	fn from(error: FileError) -> Self {
+		ControlsError::File(error)
+	}
+}
+
+impl KeyBindings {
+	pub fn paddle(&self, player: Player) -> &PaddleKeys {
+		match player {
+			Player::Left => &self.left,
+			Player::Right => &self.right,
+		}
+	}
+
+	pub fn key(&self, action: Action) -> KeyCode {
+		match action {
+			Action::Up(player) => self.paddle(player).up,
+			Action::Down(player) => self.paddle(player).down,
+			Action::Serve(player) => self.paddle(player).serve,
+			Action::Pause => self.pause,
+			Action::Reset => self.reset,
+			Action::Quit => self.quit,
+		}
+	}
+
+	fn paddle_mut(&mut self, player: Player) -> &mut PaddleKeys {
+		match player {
+			Player::Left => &mut self.left,
+			Player::Right => &mut self.right,
+		}
+	}
+
+	fn key_mut(&mut self, action: Action) -> &mut KeyCode {
+		match action {
+			Action::Up(player) => &mut self.paddle_mut(player).up,
+			Action::Down(player) => &mut self.paddle_mut(player).down,
+			Action::Serve(player) => &mut self.paddle_mut(player).serve,
+			Action::Pause => &mut self.pause,
+			Action::Reset => &mut self.reset,
+			Action::Quit => &mut self.quit,
+		}
+	}
+
+	/// The action `key` is bound to, if any.
+	pub fn action(&self, key: KeyCode) -> Option<Action> {
+		Action::ALL.into_iter().find(|action| self.key(*action) == key)
+	}
+
+	/// Binds `key` to `action`, unless another action already has it, which is returned instead.
+	pub fn bind(&mut self, action: Action, key: KeyCode) -> Result<(), Action> {
+		match self.action(key) {
+			Some(other) if other != action => Err(other),
+			_ => {
+				*self.key_mut(action) = key;
+				Ok(())
+			}
+		}
+	}
+
+	/// The first two actions sharing a key, which only a hand-edited file can have.
+	pub fn conflict(&self) -> Option<(Action, Action)> {
+		Action::ALL.iter().enumerate().find_map(|(index, first)| {
+			Action::ALL[index + 1..]
+				.iter()
+				.find(|second| self.key(**second) == self.key(*first))
+				.map(|second| (*first, *second))
+		})
+	}
+
+	pub fn pressed(&self, keys: &Input<KeyCode>, action: Action) -> bool {
+		keys.pressed(self.key(action))
+	}
+
+	pub fn just_pressed(&self, keys: &Input<KeyCode>, action: Action) -> bool {
+		keys.just_pressed(self.key(action))
+	}
+
+	pub fn save(&self, path: &Path) -> Result<(), ControlsError> {
+		Ok(files::write_json(path, self)?)
+	}
+
+	pub fn load(path: &Path) -> Result<Self, ControlsError> {
+		let bindings: Self = files::read_json(path)?;
+		match bindings.conflict() {
+			Some((first, second)) => Err(ControlsError::Conflict(first, second)),
+			None => Ok(bindings),
+		}
+	}
+}
+
+/// Where the [`KeyBindings`] are kept between runs.
+#[derive(Clone, Debug, Resource)]
+pub struct ControlsConfig {
+	/// `None` forgets changed bindings when the game closes.
+	pub path: Option<PathBuf>,
+}
+
+impl Default for ControlsConfig {
+	fn default() -> Self {
+		Self {
+			path: files::data_path("controls.json"),
+		}
+	}
+}
+
+/// Provides the default [`KeyBindings`], [`ControlsScreenPlugin`] loads the saved ones.
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<KeyBindings>().init_resource::<ControlsConfig>();
+	}
+}
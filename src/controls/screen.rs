@@ -0,0 +1,230 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::GameState;
+
+use super::{Action, ControlsConfig, ControlsError, KeyBindings};
+
+const TEXT_COLOR: Color = Color::WHITE;
+const SELECTED_COLOR: Color = Color::rgb(1., 0.8, 0.2);
+
+#[derive(Component)]
+struct ControlsScreen;
+
+/// The text showing the key of the [`Action`] at this index of [`Action::ALL`].
+#[derive(Component)]
+struct ActionRow(usize);
+
+/// The text telling what happened to the last binding.
+#[derive(Component)]
+struct StatusText;
+
+/// The state of the controls screen.
+#[derive(Debug, Default, Resource)]
+struct ControlsMenu {
+	/// Index of the highlighted row in [`Action::ALL`].
+	selected: usize,
+	/// Whether the next key pressed gets bound to the highlighted action.
+	binding: bool,
+	status: String,
+}
+
+/// Loads the saved [`KeyBindings`] and shows a screen to change them, opened with [`Self::OPEN_KEY`] from the
+/// menu. Also closes the game on [`Action::Quit`].
+pub struct ControlsScreenPlugin;
+impl Plugin for ControlsScreenPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ControlsMenu>()
+			.add_startup_system(Self::load)
+			.add_system(Self::setup.in_schedule(OnEnter(GameState::Controls)))
+			.add_system(Self::cleanup.in_schedule(OnExit(GameState::Controls)))
+			.add_system(Self::open.in_set(OnUpdate(GameState::Menu)))
+			.add_systems(
+				(Self::handle_input, Self::update_rows)
+					.chain()
+					.in_set(OnUpdate(GameState::Controls)),
+			)
+			.add_system(Self::quit);
+	}
+}
+
+impl ControlsScreenPlugin {
+	/// Opens the controls screen from the menu, it doesn't start a match like any other key.
+	pub const OPEN_KEY: KeyCode = KeyCode::C;
+
+	fn load(mut bindings: ResMut<KeyBindings>, config: Res<ControlsConfig>) {
+		let Some(path) = &config.path else {
+			return;
+		};
+
+		match KeyBindings::load(path) {
+			Ok(loaded) => *bindings = loaded,
+			// Nothing was ever rebound
+			Err(ControlsError::File(error)) if error.is_not_found() => {}
+			Err(error) => warn!("Using the default key bindings, {} ({})", error, path.display()),
+		}
+	}
+
+	fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut menu: ResMut<ControlsMenu>) {
+		*menu = ControlsMenu::default();
+		let font = asset_server.load("fonts/Pixelzim 3x5.ttf");
+		let style = |font_size| TextStyle {
+			color: TEXT_COLOR,
+			font_size,
+			font: font.clone(),
+		};
+
+		commands
+			.spawn((
+				Name::new("Controls Screen"),
+				ControlsScreen,
+				NodeBundle {
+					background_color: BackgroundColor(Color::rgba(0., 0., 0., 0.9)),
+					style: Style {
+						align_items: AlignItems::Center,
+						flex_direction: FlexDirection::Column,
+						justify_content: JustifyContent::Center,
+						position_type: PositionType::Absolute,
+						size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+						..default()
+					},
+					..default()
+				},
+			))
+			.with_children(|commands| {
+				commands.spawn((
+					Name::new("Controls Screen Text"),
+					TextBundle::from_section("Controls", style(80.)).with_style(Style {
+						margin: UiRect::bottom(Val::Px(20.)),
+						..default()
+					}),
+				));
+				for index in 0..Action::ALL.len() {
+					commands.spawn((
+						Name::new(format!("Controls Screen {}", Action::ALL[index])),
+						ActionRow(index),
+						TextBundle::from_section("", style(40.)),
+					));
+				}
+				commands.spawn((
+					Name::new("Controls Screen Status"),
+					StatusText,
+					TextBundle::from_section("", style(30.)).with_style(Style {
+						margin: UiRect::top(Val::Px(20.)),
+						..default()
+					}),
+				));
+				commands.spawn((
+					Name::new("Controls Screen Options"),
+					TextBundle::from_section(
+						"UP/DOWN: select   ENTER: bind   BACKSPACE: defaults   ESC: back",
+						style(30.),
+					),
+				));
+			});
+	}
+
+	fn cleanup(mut commands: Commands, query: Query<Entity, With<ControlsScreen>>) {
+		for entity in query.iter() {
+			commands.entity(entity).despawn_recursive();
+		}
+	}
+
+	fn open(mut next_state: ResMut<NextState<GameState>>, keyboard_input: Res<Input<KeyCode>>) {
+		if keyboard_input.just_pressed(Self::OPEN_KEY) {
+			next_state.set(GameState::Controls);
+		}
+	}
+
+	/// Navigates the screen with fixed keys, so a botched binding can always be fixed.
+	fn handle_input(
+		mut menu: ResMut<ControlsMenu>,
+		mut bindings: ResMut<KeyBindings>,
+		mut next_state: ResMut<NextState<GameState>>,
+		config: Res<ControlsConfig>,
+		keyboard_input: Res<Input<KeyCode>>,
+	) {
+		let Some(&key) = keyboard_input.get_just_pressed().next() else {
+			return;
+		};
+
+		if menu.binding {
+			let action = Action::ALL[menu.selected];
+			menu.binding = false;
+			menu.status = match bindings.bind(action, key) {
+				Ok(()) => Self::save(&bindings, &config),
+				Err(other) => format!("{:?} is already bound to {}", key, other),
+			};
+			return;
+		}
+
+		match key {
+			KeyCode::Up => menu.selected = menu.selected.checked_sub(1).unwrap_or(Action::ALL.len() - 1),
+			KeyCode::Down => menu.selected = (menu.selected + 1) % Action::ALL.len(),
+			KeyCode::Return => {
+				menu.binding = true;
+				menu.status.clear();
+			}
+			KeyCode::Back => {
+				*bindings = KeyBindings::default();
+				menu.status = Self::save(&bindings, &config);
+			}
+			KeyCode::Escape => next_state.set(GameState::Menu),
+			_ => {}
+		}
+	}
+
+	/// Saves the bindings, returns what to tell the player about it.
+	fn save(bindings: &KeyBindings, config: &ControlsConfig) -> String {
+		let Some(path) = &config.path else {
+			return "Changed until the game closes".to_owned();
+		};
+
+		match bindings.save(path) {
+			Ok(()) => "Saved".to_owned(),
+			Err(error) => {
+				error!("Failed to save the key bindings to {}: {}", path.display(), error);
+				"Changed, but couldn't be saved".to_owned()
+			}
+		}
+	}
+
+	fn update_rows(
+		menu: Res<ControlsMenu>,
+		bindings: Res<KeyBindings>,
+		mut row_query: Query<(&mut Text, &ActionRow)>,
+		mut status_query: Query<&mut Text, (With<StatusText>, Without<ActionRow>)>,
+	) {
+		if !menu.is_changed() && !bindings.is_changed() {
+			return;
+		}
+
+		for (mut text, ActionRow(index)) in row_query.iter_mut() {
+			let action = Action::ALL[*index];
+			let selected = *index == menu.selected;
+			let section = &mut text.sections[0];
+
+			section.value = if selected && menu.binding {
+				format!("{:<14}press a key", action.to_string())
+			} else {
+				format!("{:<14}{:?}", action.to_string(), bindings.key(action))
+			};
+			section.style.color = if selected { SELECTED_COLOR } else { TEXT_COLOR };
+		}
+
+		for mut text in status_query.iter_mut() {
+			text.sections[0].value = menu.status.clone();
+		}
+	}
+
+	/// Closes the game, except on the controls screen where every key may be getting bound.
+	fn quit(
+		bindings: Res<KeyBindings>,
+		state: Res<State<GameState>>,
+		keyboard_input: Res<Input<KeyCode>>,
+		mut exit_writer: EventWriter<AppExit>,
+	) {
+		if state.0 != GameState::Controls && bindings.just_pressed(&keyboard_input, Action::Quit) {
+			exit_writer.send(AppExit);
+		}
+	}
+}
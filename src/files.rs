@@ -0,0 +1,91 @@
+//! Where the game keeps its files and how it reads and writes them, see [`FileError`].
+
+use std::{
+	fmt,
+	fs::{self, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// `name` relative to where the game runs, or `None` on the web, which has no file system to write to.
+pub fn data_path(name: &str) -> Option<PathBuf> {
+	(!cfg!(target_arch = "wasm32")).then(|| PathBuf::from(name))
+}
+
+/// Why a file couldn't be read or written.
+#[derive(Debug)]
+pub enum FileError {
+	Io(io::Error),
+	Json(serde_json::Error),
+}
+
+impl FileError {
+	/// Whether the file doesn't exist, e.g. because nothing was ever saved.
+	pub fn is_not_found(&self) -> bool {
+		matches!(self, FileError::Io(error) if error.kind() == io::ErrorKind::NotFound)
+	}
+
+	/// Describes the error for a file holding `what`, e.g. "the replay".
+	pub fn describe(&self, formatter: &mut fmt::Formatter, what: &str) -> fmt::Result {
+		match self {
+			FileError::Io(error) => write!(formatter, "couldn't access {}: {}", what, error),
+			FileError::Json(error) => write!(formatter, "{} is damaged: {}", what, error),
+		}
+	}
+}
+
+impl fmt::Display for FileError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		self.describe(formatter, "the file")
+	}
+}
+
+impl std::error::Error for FileError {}
+
+impl From<io::Error> for FileError {
+	fn from(error: io::Error) -> Self {
+		FileError::Io(error)
+	}
+}
+
+impl From<serde_json::Error> for FileError {
+	fn from(error: serde_json::Error) -> Self {
+		FileError::Json(error)
+	}
+}
+
+pub fn read(path: &Path) -> Result<String, FileError> {
+	Ok(fs::read_to_string(path)?)
+}
+
+fn create_directory(path: &Path) -> Result<(), FileError> {
+	if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+		fs::create_dir_all(directory)?;
+	}
+	Ok(())
+}
+
+/// Writes `contents` to `path`, creating the directory it's in first.
+pub fn write(path: &Path, contents: &str) -> Result<(), FileError> {
+	create_directory(path)?;
+	Ok(fs::write(path, contents)?)
+}
+
+/// Adds `value` to the end of `path` as a line of JSON, creating the file and the directory it's in first.
+pub fn append_json_line<T: Serialize>(path: &Path, value: &T) -> Result<(), FileError> {
+	let line = serde_json::to_string(value)?;
+	create_directory(path)?;
+	let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+	Ok(writeln!(file, "{}", line)?)
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, FileError> {
+	Ok(serde_json::from_str(&read(path)?)?)
+}
+
+/// Writes `value` as indented JSON, so the file can be edited by hand.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), FileError> {
+	write(path, &serde_json::to_string_pretty(value)?)
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+
+use crate::{
+	controls::{Action, KeyBindings},
+	player::Player,
+	replay::{OpenReplay, Recorder},
+	reset::Reset,
+	rules::MatchResult,
+	GameState,
+};
+
+#[derive(Component)]
+pub struct GameOver;
+
+pub struct GameOverPlugin;
+impl Plugin for GameOverPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(Self::setup.in_schedule(OnEnter(GameState::GameOver)))
+			.add_system(Self::cleanup.in_schedule(OnExit(GameState::GameOver)))
+			.add_system(Self::handle_input.in_set(OnUpdate(GameState::GameOver)));
+	}
+}
+
+impl GameOverPlugin {
+	pub fn setup(
+		mut commands: Commands,
+		asset_server: Res<AssetServer>,
+		result: Option<Res<MatchResult>>,
+		recorder: Res<Recorder>,
+		bindings: Res<KeyBindings>,
+	) {
+		let font = asset_server.load("fonts/Pixelzim 3x5.ttf");
+		let headline = match result.as_ref().map(|result| result.winner) {
+			Some(Player::Left) => "Left player wins",
+			Some(Player::Right) => "Right player wins",
+			None => "Game over",
+		};
+		let rematch = format!("{:?}: rematch", bindings.key(Action::Reset));
+		let options = if recorder.last.is_some() {
+			format!("{}   V: watch replay   M: menu", rematch)
+		} else {
+			format!("{}   M: menu", rematch)
+		};
+
+		commands
+			.spawn((
+				Name::new("Game Over Screen"),
+				GameOver,
+				NodeBundle {
+					background_color: BackgroundColor(Color::rgba(0., 0., 0., 0.9)),
+					style: Style {
+						align_items: AlignItems::Center,
+						flex_direction: FlexDirection::Column,
+						justify_content: JustifyContent::Center,
+						position_type: PositionType::Absolute,
+						size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+						..default()
+					},
+					..default()
+				},
+			))
+			.with_children(|commands| {
+				commands.spawn((
+					Name::new("Game Over Screen Text"),
+					TextBundle::from_section(
+						headline,
+						TextStyle {
+							color: Color::WHITE,
+							font_size: 100.0,
+							font: font.clone(),
+						},
+					),
+				));
+				if let Some(result) = result.as_deref() {
+					commands.spawn((
+						Name::new("Game Over Screen Score"),
+						TextBundle::from_section(
+							format!("{:0>2}   {:0>2}", result.left, result.right),
+							TextStyle {
+								color: Color::WHITE,
+								font_size: 100.0,
+								font: font.clone(),
+							},
+						),
+					));
+				}
+				commands.spawn((
+					Name::new("Game Over Screen Options"),
+					TextBundle::from_section(
+						options,
+						TextStyle {
+							color: Color::WHITE,
+							font_size: 50.0,
+							font,
+						},
+					),
+				));
+			});
+	}
+
+	pub fn cleanup(mut commands: Commands, mut query: Query<Entity, With<GameOver>>) {
+		for entity in query.iter_mut() {
+			commands.entity(entity).despawn_recursive();
+		}
+	}
+
+	pub fn handle_input(
+		mut next_state: ResMut<NextState<GameState>>,
+		mut reset_writer: EventWriter<Reset>,
+		mut open_writer: EventWriter<OpenReplay>,
+		keyboard_input: Res<Input<KeyCode>>,
+		touches: Res<Touches>,
+		bindings: Res<KeyBindings>,
+	) {
+		if keyboard_input.just_pressed(KeyCode::V) {
+			open_writer.send(OpenReplay::Last);
+		} else if bindings.just_pressed(&keyboard_input, Action::Reset) || touches.any_just_pressed() {
+			reset_writer.send(Reset::Hard);
+			next_state.set(GameState::WaitBeforeRound);
+		} else if keyboard_input.just_pressed(KeyCode::M) {
+			reset_writer.send(Reset::Hard);
+			next_state.set(GameState::Menu);
+		}
+	}
+}
@@ -0,0 +1,138 @@
+use bevy::{
+	input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+	prelude::*,
+};
+
+use crate::{
+	controller::Controller,
+	paddle::{PaddleInput, PaddlePlugin},
+	player::Player,
+	GameSet,
+};
+
+/// Which gamepad plays which side. Gamepads are handed out as they're plugged in, left side first.
+#[derive(Debug, Default, Resource)]
+pub struct GamepadAssignments {
+	pub left: Option<Gamepad>,
+	pub right: Option<Gamepad>,
+}
+
+impl GamepadAssignments {
+	pub fn get(&self, player: Player) -> Option<Gamepad> {
+		match player {
+			Player::Left => self.left,
+			Player::Right => self.right,
+		}
+	}
+
+	fn slot(&mut self, player: Player) -> &mut Option<Gamepad> {
+		match player {
+			Player::Left => &mut self.left,
+			Player::Right => &mut self.right,
+		}
+	}
+
+	fn is_assigned(&self, gamepad: Gamepad) -> bool {
+		Player::ALL.iter().any(|player| self.get(*player) == Some(gamepad))
+	}
+
+	/// Gives `gamepad` the first side without one, returns the side or `None` if both are taken.
+	fn assign(&mut self, gamepad: Gamepad) -> Option<Player> {
+		let player = Player::ALL.into_iter().find(|player| self.get(*player).is_none())?;
+		*self.slot(player) = Some(gamepad);
+		Some(player)
+	}
+}
+
+/// Lets local players play with gamepads instead of the keyboard, see [`GamepadAssignments`].
+pub struct GamepadPlugin;
+impl Plugin for GamepadPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<GamepadAssignments>()
+			.add_system(Self::assign_gamepads)
+			.add_system(
+				Self::handle_input
+					.in_set(GameSet::Input)
+					.after(PaddlePlugin::handle_input)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			);
+	}
+}
+
+impl GamepadPlugin {
+	fn assign_gamepads(
+		mut assignments: ResMut<GamepadAssignments>,
+		mut connection_events: EventReader<GamepadConnectionEvent>,
+		gamepads: Res<Gamepads>,
+	) {
+		for event in connection_events.iter() {
+			match &event.connection {
+				GamepadConnection::Connected(info) => {
+					if assignments.is_assigned(event.gamepad) {
+						continue;
+					}
+					match assignments.assign(event.gamepad) {
+						Some(player) => info!("{} plays {:?}", info.name, player),
+						None => info!("{} is connected, but both sides already have a gamepad", info.name),
+					}
+				}
+				GamepadConnection::Disconnected => {
+					for player in Player::ALL {
+						if assignments.get(player) == Some(event.gamepad) {
+							info!("The gamepad of {:?} was disconnected", player);
+							*assignments.slot(player) = None;
+						}
+					}
+
+					// A gamepad that was left over gets the free side
+					let spare = gamepads
+						.iter()
+						.find(|gamepad| *gamepad != event.gamepad && !assignments.is_assigned(*gamepad));
+					if let Some(player) = spare.and_then(|gamepad| assignments.assign(gamepad)) {
+						info!("A spare gamepad plays {:?}", player);
+					}
+				}
+			}
+		}
+	}
+
+	/// Moves the paddle proportionally to the left stick, or at full speed with the D-pad, unless the keyboard
+	/// already does.
+	pub fn handle_input(
+		assignments: Res<GamepadAssignments>,
+		buttons: Res<Input<GamepadButton>>,
+		axes: Res<Axis<GamepadAxis>>,
+		mut query: Query<(&mut PaddleInput, &Player, &Controller)>,
+	) {
+		for (mut input, player, controller) in query.iter_mut() {
+			if *controller != Controller::Local {
+				continue;
+			}
+			let Some(gamepad) = assignments.get(*player) else {
+				continue;
+			};
+
+			let pressed = |button_type| buttons.pressed(GamepadButton::new(gamepad, button_type));
+			let axis = if pressed(GamepadButtonType::DPadUp) {
+				1.
+			} else if pressed(GamepadButtonType::DPadDown) {
+				-1.
+			} else {
+				axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+					.unwrap_or_default()
+			};
+
+			if axis != 0. {
+				input.axis = axis.clamp(-1., 1.);
+			}
+			input.serve |= pressed(GamepadButtonType::South);
+		}
+	}
+
+	/// Whether any gamepad's `button_type` was just pressed.
+	pub fn just_pressed(buttons: &Input<GamepadButton>, button_type: GamepadButtonType) -> bool {
+		buttons
+			.get_just_pressed()
+			.any(|button| button.button_type == button_type)
+	}
+}
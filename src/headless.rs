@@ -0,0 +1,266 @@
+//! Running the game without a window, e.g. to play thousands of bot matches.
+
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use serde::Serialize;
+
+use crate::{
+	ai_paddle::AiDifficulty,
+	ball::{BallPhysics, CollisionEvent},
+	config::GameConfig,
+	controller::{Controller, Controllers},
+	player::Player,
+	replay::ReplayConfig,
+	reset::Reset,
+	rules::{MatchResult, MatchRules},
+	score::Score,
+	script::{InputCapture, InputScript},
+	simulation::{Phase, Simulation},
+	GameSet, GameState, PongPlugin,
+};
+
+/// An [`App`] running the game without window, renderer or audio.
+/// Every [`App::update`] advances it by exactly one fixed step, however long the update took.
+pub fn app(seed: u64) -> App {
+	let mut app = App::new();
+	app.add_plugins(MinimalPlugins)
+		.add_plugin(InputPlugin)
+		.add_plugin(PongPlugin {
+			seed: Some(seed),
+			headless: true,
+		});
+
+	let period = app.world.resource::<FixedTime>().period;
+	app.insert_resource(TimeUpdateStrategy::ManualDuration(period))
+		.insert_resource(ReplayConfig { directory: None });
+	app
+}
+
+/// A bot-vs-bot match, see [`HeadlessMatch::run`].
+#[derive(Clone, Debug)]
+pub struct HeadlessMatch {
+	pub seed: u64,
+	pub rules: MatchRules,
+	pub physics: BallPhysics,
+	pub config: GameConfig,
+	pub left: AiDifficulty,
+	pub right: AiDifficulty,
+	/// Gives up on a match after this many steps.
+	pub max_ticks: u64,
+}
+
+impl Default for HeadlessMatch {
+	fn default() -> Self {
+		Self {
+			seed: 0,
+			rules: MatchRules::default(),
+			physics: BallPhysics::default(),
+			config: GameConfig::default(),
+			left: AiDifficulty::default(),
+			right: AiDifficulty::default(),
+			// An hour of play
+			max_ticks: 60 * 60 * 60,
+		}
+	}
+}
+
+/// How a [`HeadlessMatch`] went.
+#[derive(Debug, Serialize)]
+pub struct MatchReport {
+	pub seed: u64,
+	pub left: AiDifficulty,
+	pub right: AiDifficulty,
+	/// `None` if the match ran out of [`HeadlessMatch::max_ticks`].
+	pub winner: Option<Player>,
+	pub score: Score,
+	/// Paddle hits of every point, in the order they were played.
+	pub rallies: Vec<usize>,
+	pub longest_rally: usize,
+	pub ticks: u64,
+	/// Seconds of game time the match took.
+	pub duration: f32,
+}
+
+/// Paddle hits of every point played so far.
+#[derive(Debug, Default, Resource)]
+struct RallyLog {
+	hits: usize,
+	rallies: Vec<usize>,
+}
+
+/// Plays by `config`, still advancing by exactly one fixed step per update.
+fn insert_config(app: &mut App, config: &GameConfig) {
+	let time_step = Duration::from_secs_f32(config.time_step);
+	app.insert_resource(config.clone())
+		.insert_resource(TimeUpdateStrategy::ManualDuration(time_step));
+}
+
+/// Starts a match like the splash screen does and plays it until it's over or ran for `max_ticks` steps.
+fn play(app: &mut App, max_ticks: u64) {
+	// Run the startup systems, then leave the menu
+	app.update();
+	app.world.resource_mut::<Events<Reset>>().send(Reset::Hard);
+	app.world
+		.resource_mut::<NextState<GameState>>()
+		.set(GameState::WaitBeforeRound);
+
+	loop {
+		app.update();
+
+		let state = &app.world.resource::<Simulation>().state;
+		if !state.attract && (matches!(state.phase, Phase::GameOver { .. }) || state.tick >= max_ticks) {
+			break;
+		}
+	}
+}
+
+impl HeadlessMatch {
+	/// Plays the match as fast as possible.
+	pub fn run(&self) -> MatchReport {
+		let mut app = app(self.seed);
+		insert_config(&mut app, &self.config);
+		app.insert_resource(self.rules.clone())
+			.insert_resource(self.physics.clone())
+			.insert_resource(Controllers {
+				left: Controller::Ai(self.left),
+				right: Controller::Ai(self.right),
+			})
+			.init_resource::<RallyLog>()
+			.add_system(
+				Self::log_rallies
+					.after(GameSet::Movement)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			);
+
+		play(&mut app, self.max_ticks);
+
+		let simulation = app.world.resource::<Simulation>();
+		let state = &simulation.state;
+		MatchReport {
+			seed: self.seed,
+			left: self.left,
+			right: self.right,
+			winner: app.world.get_resource::<MatchResult>().map(|result| result.winner),
+			score: state.score,
+			rallies: app.world.resource::<RallyLog>().rallies.clone(),
+			longest_rally: state.rally.longest,
+			ticks: state.tick,
+			duration: state.tick as f32 * simulation.settings.config.time_step,
+		}
+	}
+
+	fn log_rallies(
+		mut collision_events: EventReader<CollisionEvent>,
+		mut log: ResMut<RallyLog>,
+		simulation: Res<Simulation>,
+	) {
+		// The attract mode running before the match starts doesn't count
+		if simulation.state.attract {
+			collision_events.clear();
+			return;
+		}
+
+		for collision_event in collision_events.iter() {
+			match collision_event {
+				CollisionEvent::Paddle { .. } => log.hits += 1,
+				CollisionEvent::Goal { .. } => {
+					let hits = std::mem::take(&mut log.hits);
+					log.rallies.push(hits);
+				}
+				CollisionEvent::Wall { .. } => {}
+			}
+		}
+	}
+}
+
+/// A match played by an [`InputScript`] on both sides, see [`ScriptedMatch::run`].
+#[derive(Clone, Debug)]
+pub struct ScriptedMatch {
+	pub seed: u64,
+	pub rules: MatchRules,
+	pub physics: BallPhysics,
+	pub config: GameConfig,
+	pub script: InputScript,
+	/// Gives up on a match after this many steps.
+	pub max_ticks: u64,
+}
+
+impl Default for ScriptedMatch {
+	fn default() -> Self {
+		Self {
+			seed: 0,
+			rules: MatchRules::default(),
+			physics: BallPhysics::default(),
+			config: GameConfig::default(),
+			script: InputScript::default(),
+			max_ticks: HeadlessMatch::default().max_ticks,
+		}
+	}
+}
+
+/// How a [`ScriptedMatch`] went.
+#[derive(Debug)]
+pub struct ScriptReport {
+	/// `None` if the match ran out of [`ScriptedMatch::max_ticks`].
+	pub winner: Option<Player>,
+	pub score: Score,
+	/// Everything the ball hit, in order.
+	pub collisions: Vec<CollisionEvent>,
+	pub ticks: u64,
+	/// The input as it was captured while playing, see [`InputCapture`].
+	pub captured: InputScript,
+}
+
+/// Everything the ball hit during the match.
+#[derive(Debug, Default, Resource)]
+struct CollisionLog(Vec<CollisionEvent>);
+
+impl ScriptedMatch {
+	/// Plays the match as fast as possible.
+	pub fn run(&self) -> ScriptReport {
+		let mut app = app(self.seed);
+		insert_config(&mut app, &self.config);
+		app.insert_resource(self.rules.clone())
+			.insert_resource(self.physics.clone())
+			.insert_resource(self.script.clone())
+			.insert_resource(Controllers {
+				left: Controller::Script,
+				right: Controller::Script,
+			})
+			.init_resource::<CollisionLog>()
+			.add_system(
+				Self::log_collisions
+					.after(GameSet::Movement)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			);
+
+		play(&mut app, self.max_ticks);
+
+		let capture = app.world.resource::<InputCapture>();
+		ScriptReport {
+			winner: app.world.get_resource::<MatchResult>().map(|result| result.winner),
+			score: app.world.resource::<Simulation>().state.score,
+			collisions: app.world.resource::<CollisionLog>().0.clone(),
+			ticks: app.world.resource::<Simulation>().state.tick,
+			captured: capture
+				.last
+				.clone()
+				.or_else(|| capture.recording.clone())
+				.unwrap_or_default(),
+		}
+	}
+
+	fn log_collisions(
+		mut collision_events: EventReader<CollisionEvent>,
+		mut log: ResMut<CollisionLog>,
+		simulation: Res<Simulation>,
+	) {
+		// The attract mode running before the match starts doesn't count
+		if simulation.state.attract {
+			collision_events.clear();
+			return;
+		}
+		log.0.extend(collision_events.iter().copied());
+	}
+}
@@ -1,41 +1,93 @@
 #[cfg(feature = "debug")]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::ScalingMode};
 
 use bevy_turborand::RngPlugin;
+use serde::{Deserialize, Serialize};
 
 mod ai_paddle;
 mod ball;
 mod centre_line;
+pub mod cli;
 mod collider;
+mod config;
+mod controller;
+mod controls;
+mod files;
 mod game_menu;
+mod game_over;
+mod gamepad;
+pub mod headless;
+mod match_clock;
+mod net;
 mod paddle;
 mod pause;
 mod player;
+mod pointer;
+mod power_up;
+pub mod preset;
+mod replay;
 mod reset;
+mod rules;
 mod score;
+pub mod script;
+mod serve;
+pub mod server;
 mod sfxr_audio;
+mod simulation;
 mod splash_screen;
 mod wall;
 
 use ai_paddle::AiPaddlePlugin;
 use ball::BallPlugin;
 use centre_line::CentreLinePlugin;
+use config::{ConfigAssetPlugin, ConfigPlugin};
+use controller::ControllerPlugin;
+use controls::{ControlsPlugin, ControlsScreenPlugin};
+use game_over::GameOverPlugin;
+use gamepad::GamepadPlugin;
+use match_clock::MatchClockPlugin;
+use net::NetPlugin;
 use paddle::PaddlePlugin;
 use pause::PausePlugin;
+use pointer::PointerPlugin;
+use power_up::PowerUpPlugin;
+use preset::PresetPlugin;
+use replay::{ReplayPlugin, ReplayViewerPlugin};
 use reset::ResetPlugin;
 use score::ScorePlugin;
+use script::ScriptPlugin;
+use serve::ServePlugin;
 use sfxr_audio::SfxrAudioPlugin;
+use simulation::SimulationPlugin;
 use splash_screen::SplashScreenPlugin;
 use wall::WallPlugin;
 
-#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub use ai_paddle::AiDifficulty;
+pub use ball::{BallPhysics, CollisionEvent, SpinPhysics};
+pub use config::{ConfigError, GameConfig};
+pub use controller::{Controller, Controllers};
+pub use files::FileError;
+pub use net::{NetMode, NetSettings, NetworkConditions};
+pub use player::Player;
+pub use power_up::PowerUpPhysics;
+pub use replay::{OpenReplay, Replay, ReplayConfig, ReplayError};
+pub use rules::{MatchRules, ServeRule};
+pub use score::Score;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Eq, PartialEq, Hash, Serialize, States)]
 pub enum GameState {
 	#[default]
 	Menu,
 	WaitBeforeRound,
+	Serve,
 	Playing,
 	Paused,
+	GameOver,
+	/// Watching a [`Replay`].
+	Replay,
+	/// Rebinding keys, see [`controls::KeyBindings`].
+	Controls,
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, SystemSet)]
@@ -44,35 +96,79 @@ pub enum GameSet {
 	Movement,
 	CollisionDetection,
 	Reset,
+	/// Copies the [`simulation::Simulation`] into the entities and resources that show it.
+	Sync,
 }
 
 pub const WINDOW_HEIGHT: f32 = 720.;
 pub const WINDOW_WIDTH: f32 = 1280.;
-const TIME_STEP: f32 = 1.0 / 60.0;
+pub(crate) const TIME_STEP: f32 = 1.0 / 60.0;
+
+#[derive(Default)]
+pub struct PongPlugin {
+	/// Seeds all of the game's randomness, `None` for a random seed.
+	pub seed: Option<u64>,
+	/// Leaves out everything that needs a window, fonts or an audio device, see [`headless`].
+	pub headless: bool,
+}
 
-pub struct PongPlugin;
 impl Plugin for PongPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_startup_system(Self::setup)
-			.add_state::<GameState>()
-			.add_plugin(RngPlugin::default())
+		let rng_plugin = match self.seed {
+			Some(seed) => RngPlugin::new().with_rng_seed(seed),
+			None => RngPlugin::default(),
+		};
+
+		app.add_state::<GameState>()
+			.add_plugin(rng_plugin)
+			.add_plugin(ConfigPlugin)
+			.add_plugin(PresetPlugin)
+			.add_plugin(ControlsPlugin)
 			.add_plugin(ResetPlugin)
-			.add_plugin(SfxrAudioPlugin)
-			.add_plugin(CentreLinePlugin)
+			.add_plugin(SimulationPlugin)
+			.add_plugin(ReplayPlugin)
 			.add_plugin(BallPlugin)
+			.add_plugin(ServePlugin)
 			.add_plugin(PaddlePlugin)
-			//.add_plugin(AiPaddlePlugin)
-			.add_plugin(PausePlugin)
-			.add_plugin(ScorePlugin)
-			.add_plugin(SplashScreenPlugin)
+			.add_plugin(ControllerPlugin)
+			.add_plugin(ScriptPlugin)
+			.add_plugin(GamepadPlugin)
+			.add_plugin(AiPaddlePlugin)
 			.add_plugin(WallPlugin)
+			.add_plugin(NetPlugin)
 			.configure_set(GameSet::Input.before(GameSet::Movement))
 			.configure_set(GameSet::CollisionDetection)
-			.configure_set(GameSet::Movement.after(GameSet::CollisionDetection).after(GameSet::Input))
+			.configure_set(
+				GameSet::Movement
+					.after(GameSet::CollisionDetection)
+					.after(GameSet::Input),
+			)
 			.configure_set(GameSet::Reset.after(GameSet::CollisionDetection))
+			.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+				schedule
+					.configure_set(GameSet::Input.before(GameSet::Movement))
+					.configure_set(GameSet::Sync.after(GameSet::Movement));
+			})
+			.init_resource::<MatchRules>()
 			.insert_resource(FixedTime::new_from_secs(TIME_STEP))
 			.insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.1)));
 
+		if !self.headless {
+			app.add_startup_system(Self::setup)
+				.add_plugin(ConfigAssetPlugin)
+				.add_plugin(SfxrAudioPlugin)
+				.add_plugin(CentreLinePlugin)
+				.add_plugin(ControlsScreenPlugin)
+				.add_plugin(PausePlugin)
+				.add_plugin(PointerPlugin)
+				.add_plugin(PowerUpPlugin)
+				.add_plugin(GameOverPlugin)
+				.add_plugin(ScorePlugin)
+				.add_plugin(MatchClockPlugin)
+				.add_plugin(ReplayViewerPlugin)
+				.add_plugin(SplashScreenPlugin);
+		}
+
 		#[cfg(feature = "debug")]
 		app.add_plugin(FrameTimeDiagnosticsPlugin::default())
 			.add_plugin(LogDiagnosticsPlugin::default())
@@ -81,7 +177,17 @@ impl Plugin for PongPlugin {
 
 impl PongPlugin {
 	fn setup(mut commands: Commands) {
-		commands.spawn(Camera2dBundle::default());
+		// Shows the whole field however large the window is, like a browser tab on a tablet
+		commands.spawn(Camera2dBundle {
+			projection: OrthographicProjection {
+				scaling_mode: ScalingMode::AutoMin {
+					min_width: WINDOW_WIDTH,
+					min_height: WINDOW_HEIGHT,
+				},
+				..default()
+			},
+			..default()
+		});
 	}
 
 	pub fn is_playing(state: Res<State<GameState>>) -> bool {
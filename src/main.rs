@@ -1,15 +1,170 @@
+use std::{
+	net::SocketAddr,
+	path::PathBuf,
+	process::ExitCode,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
 use bevy::{
 	log::LogPlugin,
 	prelude::*,
-	window::{WindowLevel, WindowResolution},
+	window::{WindowLevel, WindowMode, WindowResolution},
 };
 
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-use bevy_pong::{PongPlugin, WINDOW_HEIGHT, WINDOW_WIDTH};
+use bevy_pong::{
+	cli, headless::HeadlessMatch, script::ScriptConfig, AiDifficulty, Controllers, NetMode, NetSettings, OpenReplay,
+	PongPlugin, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+
+/// Who plays, see [`Controllers`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Mode {
+	/// Two players on this machine
+	Pvp,
+	/// A player against a bot
+	Pvb,
+	/// Two bots, to watch
+	Bvb,
+}
+
+/// Pong for one or two players, against bots or over the network.
+#[derive(Parser)]
+#[command(name = "pong", after_help = "The network options override PONG_HOST and PONG_JOIN.")]
+struct Args {
+	/// Width of the window, the field keeps its size and is scaled to fit
+	#[arg(
+		long,
+		value_name = "PIXELS",
+		default_value_t = WINDOW_WIDTH as u32,
+		value_parser = clap::value_parser!(u32).range(320..)
+	)]
+	width: u32,
+	/// Height of the window, the field keeps its size and is scaled to fit
+	#[arg(
+		long,
+		value_name = "PIXELS",
+		default_value_t = WINDOW_HEIGHT as u32,
+		value_parser = clap::value_parser!(u32).range(180..)
+	)]
+	height: u32,
+	/// Covers the whole screen instead of opening a window
+	#[arg(long)]
+	fullscreen: bool,
+	/// Who plays [default: pvb]
+	#[arg(long, value_enum, conflicts_with = "join")]
+	mode: Option<Mode>,
+	/// How the bots play: easy, normal, hard or perfect [default: normal]
+	#[arg(long, value_name = "LEVEL", conflicts_with = "join")]
+	difficulty: Option<AiDifficulty>,
+	/// Seeds all of the game's randomness [default: random]
+	#[arg(long, conflicts_with = "join")]
+	seed: Option<u64>,
+	#[command(flatten)]
+	preset: cli::PresetArgs,
+	/// Watches a saved replay
+	#[arg(
+		long,
+		value_name = "FILE",
+		value_parser = cli::replay,
+		conflicts_with_all = ["mode", "difficulty", "host", "join"]
+	)]
+	replay: Option<PathBuf>,
+	/// Hosts a match on the network, e.g. 0.0.0.0:7878
+	#[arg(long, value_name = "ADDRESS", conflicts_with = "join")]
+	host: Option<SocketAddr>,
+	/// Joins the match hosted at the address, e.g. 127.0.0.1:7878
+	#[arg(long, value_name = "ADDRESS")]
+	join: Option<SocketAddr>,
+	/// Plays a bot match without a window and prints its report as JSON
+	#[arg(long, conflicts_with_all = ["width", "height", "fullscreen", "replay", "host", "join"])]
+	headless: bool,
+}
+
+impl Args {
+	/// Refuses the combinations clap can't tell apart by the options' names alone.
+	fn validate(&self) -> Result<(), String> {
+		if self.headless && matches!(self.mode, Some(Mode::Pvp | Mode::Pvb)) {
+			return Err("'--headless' only plays bots, leave out '--mode' or use '--mode bvb'".to_owned());
+		}
+		if self.net().is_some() && self.mode == Some(Mode::Bvb) {
+			return Err("a network match needs a player on each side, '--mode bvb' can't be played on it".to_owned());
+		}
+		if self.mode == Some(Mode::Pvp) && self.difficulty.is_some() {
+			return Err("'--mode pvp' has no bot for '--difficulty' to apply to".to_owned());
+		}
+		Ok(())
+	}
+
+	fn net(&self) -> Option<NetMode> {
+		match (self.host, self.join) {
+			(Some(address), _) => Some(NetMode::Host(address)),
+			(_, Some(address)) => Some(NetMode::Join(address)),
+			_ => None,
+		}
+	}
+
+	fn controllers(&self) -> Controllers {
+		let difficulty = self.difficulty.unwrap_or_default();
+		match self.mode.unwrap_or(Mode::Pvb) {
+			Mode::Pvp => Controllers::human_vs_human(),
+			Mode::Pvb => Controllers::human_vs_bot(difficulty),
+			Mode::Bvb => Controllers::bot_vs_bot(difficulty),
+		}
+	}
+
+	fn window(&self) -> Window {
+		Window {
+			canvas: Some("#bevy".to_owned()),
+			fit_canvas_to_parent: true,
+			resizable: false,
+			resolution: WindowResolution::new(self.width as f32, self.height as f32),
+			mode: if self.fullscreen {
+				WindowMode::BorderlessFullscreen
+			} else {
+				WindowMode::Windowed
+			},
+			title: "pong".to_string(),
+			present_mode: bevy::window::PresentMode::Fifo,
+			window_level: WindowLevel::AlwaysOnTop,
+			..default()
+		}
+	}
+
+	/// The bot match `--headless` plays, with a seed from the clock if none was given so every run differs.
+	fn headless_match(&self) -> HeadlessMatch {
+		let preset = self.preset.preset();
+		let difficulty = self.difficulty.unwrap_or_default();
+		let seed = self.seed.unwrap_or_else(|| {
+			SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_or(0, |since| since.as_nanos() as u64)
+		});
+		HeadlessMatch {
+			seed,
+			rules: preset.rules,
+			physics: preset.physics,
+			config: self.preset.config(),
+			left: difficulty,
+			right: difficulty,
+			..HeadlessMatch::default()
+		}
+	}
+}
+
+fn main() -> ExitCode {
+	let args = Args::parse();
+	if let Err(error) = args.validate() {
+		Args::command().error(ErrorKind::ArgumentConflict, error).exit();
+	}
+
+	if args.headless {
+		return play_headless(&args.headless_match());
+	}
 
-fn main() {
 	let mut app = App::new();
 
 	app.add_plugins(
@@ -23,23 +178,43 @@ fn main() {
 				..default()
 			})
 			.set(WindowPlugin {
-				primary_window: Some(Window {
-					canvas: Some("#bevy".to_owned()),
-					resizable: false,
-					resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
-					title: "pong".to_string(),
-					present_mode: bevy::window::PresentMode::Fifo,
-					window_level: WindowLevel::AlwaysOnTop,
-					..default()
-				}),
+				primary_window: Some(args.window()),
 				..default()
 			}),
 	)
-	.add_plugin(PongPlugin)
-	.add_system(bevy::window::close_on_esc);
+	.insert_resource(args.net().unwrap_or_else(NetMode::from_env))
+	.insert_resource(NetSettings::from_env())
+	.insert_resource(ScriptConfig::from_env())
+	.insert_resource(args.controllers());
+	args.preset.presets().insert_into(&mut app);
+	app.add_plugin(PongPlugin {
+		seed: args.seed,
+		..default()
+	});
+
+	if let Some(path) = args.replay {
+		app.world
+			.resource_mut::<Events<OpenReplay>>()
+			.send(OpenReplay::File(path));
+	}
 
 	#[cfg(feature = "debug")]
 	app.add_plugin(WorldInspectorPlugin::new());
 
 	app.run();
+	ExitCode::SUCCESS
+}
+
+fn play_headless(headless_match: &HeadlessMatch) -> ExitCode {
+	let report = headless_match.run();
+	match serde_json::to_string(&report) {
+		Ok(json) => {
+			println!("{}", json);
+			ExitCode::SUCCESS
+		}
+		Err(error) => {
+			eprintln!("error: failed to serialize the report: {}", error);
+			ExitCode::FAILURE
+		}
+	}
 }
@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::GameConfig, rules::MatchRules, score::ScoreBoard, simulation::Simulation, GameSet};
+
+/// Time left in a timed match, see [`MatchRules::time_limit`]. Restarts on every [`crate::reset::Reset::Hard`].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Resource, Serialize)]
+pub struct MatchClock {
+	/// `None` if the match isn't timed.
+	pub remaining: Option<Duration>,
+	/// Set when the time ran out on a tie, the next goal wins the match.
+	pub sudden_death: bool,
+}
+
+impl MatchClock {
+	pub fn new(rules: &MatchRules) -> Self {
+		Self {
+			remaining: rules.time_limit,
+			sudden_death: false,
+		}
+	}
+
+	/// Counts down by `step`, returning `true` if the time just ran out.
+	pub fn tick(&mut self, step: Duration) -> bool {
+		let Some(remaining) = self.remaining else {
+			return false;
+		};
+		if remaining.is_zero() {
+			return false;
+		}
+
+		let remaining = remaining.saturating_sub(step);
+		self.remaining = Some(remaining);
+		remaining.is_zero()
+	}
+}
+
+impl std::fmt::Display for MatchClock {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.sudden_death {
+			return write!(formatter, "SUDDEN DEATH");
+		}
+
+		match self.remaining {
+			Some(remaining) => {
+				let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+				write!(formatter, "{}:{:0>2}", seconds / 60, seconds % 60)
+			}
+			None => Ok(()),
+		}
+	}
+}
+
+#[derive(Default, Component)]
+pub struct MatchClockText;
+
+pub struct MatchClockPlugin;
+impl Plugin for MatchClockPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_startup_system(Self::setup)
+			.init_resource::<MatchClock>()
+			.add_system(Self::sync.in_set(GameSet::Sync).in_schedule(CoreSchedule::FixedUpdate))
+			.add_system(Self::update);
+	}
+}
+
+impl MatchClockPlugin {
+	pub fn setup(
+		mut commands: Commands,
+		asset_server: Res<AssetServer>,
+		clock: Res<MatchClock>,
+		config: Res<GameConfig>,
+	) {
+		commands
+			.spawn((
+				Name::new("Match Clock"),
+				NodeBundle {
+					style: Style {
+						justify_content: JustifyContent::Center,
+						position_type: PositionType::Absolute,
+						position: UiRect {
+							top: Val::Px(ScoreBoard::bottom(&config) + 10.),
+							..default()
+						},
+						size: Size::new(Val::Percent(100.), Val::Auto),
+						..default()
+					},
+					..default()
+				},
+			))
+			.with_children(|commands| {
+				commands.spawn((
+					MatchClockText,
+					TextBundle::from_section(
+						clock.to_string(),
+						TextStyle {
+							color: Color::WHITE,
+							font_size: 40.,
+							font: asset_server.load("fonts/Pixelzim 3x5.ttf"),
+						},
+					),
+				));
+			});
+	}
+
+	pub fn sync(simulation: Res<Simulation>, mut clock: ResMut<MatchClock>) {
+		if *clock != simulation.state.clock {
+			*clock = simulation.state.clock.clone();
+		}
+	}
+
+	pub fn update(clock: Res<MatchClock>, mut query: Query<&mut Text, With<MatchClockText>>) {
+		if !clock.is_changed() {
+			return;
+		}
+
+		for mut text in query.iter_mut() {
+			if let Some(section) = text.sections.get_mut(0) {
+				section.value = clock.to_string();
+			}
+		}
+	}
+}
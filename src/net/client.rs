@@ -0,0 +1,296 @@
+use std::{collections::VecDeque, net::SocketAddr};
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+	ball::CollisionEvent,
+	controller::{Controller, Controllers},
+	paddle::PaddleInput,
+	player::Player,
+	rules::MatchResult,
+	simulation::{Phase, Simulation},
+	GameSet, GameState,
+};
+
+use super::{Message, NetPlugin, Snapshot, Socket, TIMEOUT};
+
+/// Seconds between [`Message::Join`]s or [`Message::Watch`]es while waiting for the host.
+const JOIN_INTERVAL: f64 = 0.5;
+/// Seconds between the [`Message::Watch`]es that tell the host a spectator is still there.
+const KEEP_ALIVE_INTERVAL: f64 = 1.;
+/// Steps the shown match lags behind the newest snapshot, so there's usually a later one to interpolate towards.
+const INTERPOLATION_DELAY: f32 = 3.;
+/// Steps a spectator lags behind, who can afford to wait for late snapshots to smooth out a bad connection.
+const SPECTATOR_DELAY: f32 = 30.;
+/// Snapshots kept for interpolation.
+const SNAPSHOT_BUFFER: usize = 64;
+
+/// Follows the match of a host, see [`super::NetMode::Join`] and [`super::NetMode::Watch`].
+#[derive(Resource)]
+pub struct Client {
+	socket: Socket,
+	host: SocketAddr,
+	/// Only watches the match, without a paddle.
+	spectator: bool,
+	/// Whether the host answered.
+	joined: bool,
+	/// The side the host assigned, `None` until it did and for spectators.
+	player: Option<Player>,
+	/// Seconds since startup when the host was last heard of.
+	last_heard: f64,
+	/// Seconds since startup when the last [`Message::Join`] or [`Message::Watch`] was sent.
+	last_request: f64,
+	sequence: u64,
+	/// The newest snapshots of the host's match, oldest first.
+	snapshots: VecDeque<Snapshot>,
+	/// The tick of the host's match currently shown, see [`INTERPOLATION_DELAY`].
+	render_tick: f32,
+	/// The newest tick whose collisions and [`GameState`] were passed on.
+	shown_tick: u64,
+}
+
+impl Client {
+	pub fn new(socket: Socket, host: SocketAddr) -> Self {
+		Self {
+			socket,
+			host,
+			spectator: false,
+			joined: false,
+			player: None,
+			last_heard: 0.,
+			last_request: f64::NEG_INFINITY,
+			sequence: 0,
+			snapshots: VecDeque::new(),
+			render_tick: 0.,
+			shown_tick: 0,
+		}
+	}
+
+	/// A client that watches the match without playing.
+	pub fn spectator(socket: Socket, host: SocketAddr) -> Self {
+		Self {
+			spectator: true,
+			..Self::new(socket, host)
+		}
+	}
+
+	fn send(&mut self, message: &Message) {
+		self.socket.send(message, self.host);
+	}
+
+	/// Forgets the host, to join again.
+	fn disconnect(&mut self) {
+		self.joined = false;
+		self.player = None;
+		self.snapshots.clear();
+		self.shown_tick = 0;
+	}
+
+	fn delay(&self) -> f32 {
+		if self.spectator {
+			SPECTATOR_DELAY
+		} else {
+			INTERPOLATION_DELAY
+		}
+	}
+}
+
+pub struct ClientPlugin;
+impl Plugin for ClientPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(
+			(Self::receive, Self::check_timeout)
+				.chain()
+				.distributive_run_if(NetPlugin::is_client),
+		)
+		.add_systems(
+			(
+				Self::send.after(GameSet::Input).before(GameSet::Movement),
+				Self::interpolate.in_set(GameSet::Movement),
+			)
+				.distributive_run_if(NetPlugin::is_client)
+				.in_schedule(CoreSchedule::FixedUpdate),
+		)
+		.add_system(Self::leave.in_base_set(CoreSet::Last).run_if(NetPlugin::is_client));
+	}
+}
+
+impl ClientPlugin {
+	fn receive(mut client: ResMut<Client>, mut controllers: ResMut<Controllers>, time: Res<Time>) {
+		let now = time.elapsed_seconds_f64();
+
+		for (message, from) in client.socket.receive() {
+			if from != client.host {
+				continue;
+			}
+			client.last_heard = now;
+
+			match message {
+				Message::Welcome { player } if !client.joined && !client.spectator => {
+					info!("Joined {} as {:?}", from, player);
+					client.joined = true;
+					client.player = Some(player);
+					controllers.set(player, Controller::Local);
+					controllers.set(player.opponent(), Controller::Remote);
+				}
+				Message::Watching if !client.joined && client.spectator => {
+					info!("Watching {}", from);
+					client.joined = true;
+					controllers.left = Controller::Remote;
+					controllers.right = Controller::Remote;
+				}
+				Message::Full => error!("The match at {} is full", from),
+				Message::Leave => {
+					warn!("{} stopped hosting, trying to join again", from);
+					client.disconnect();
+				}
+				Message::Snapshot(snapshot) => {
+					// The host starts every match with a new seed, only older states of the same match are stale
+					if let Some(newest) = client.snapshots.back().map(|newest| &newest.state) {
+						if newest.seed == snapshot.state.seed && newest.tick >= snapshot.state.tick {
+							continue;
+						}
+						if newest.seed != snapshot.state.seed {
+							client.snapshots.clear();
+							client.shown_tick = 0;
+						}
+					}
+
+					client.snapshots.push_back(snapshot);
+					if client.snapshots.len() > SNAPSHOT_BUFFER {
+						client.snapshots.pop_front();
+					}
+				}
+				_ => {}
+			}
+		}
+
+		// Players keep the host posted with their inputs, spectators have to say they're still there
+		let interval = if client.joined {
+			KEEP_ALIVE_INTERVAL
+		} else {
+			JOIN_INTERVAL
+		};
+		if (!client.joined || client.spectator) && now - client.last_request > interval {
+			client.last_request = now;
+			let message = if client.spectator {
+				Message::Watch
+			} else {
+				Message::Join
+			};
+			client.send(&message);
+		}
+	}
+
+	fn check_timeout(mut client: ResMut<Client>, time: Res<Time>) {
+		if client.joined && time.elapsed_seconds_f64() - client.last_heard > TIMEOUT {
+			warn!("Lost the connection to {}, trying to join again", client.host);
+			client.disconnect();
+		}
+	}
+
+	/// Tells the host when quitting.
+	fn leave(mut client: ResMut<Client>, exit_reader: EventReader<AppExit>) {
+		if !exit_reader.is_empty() && client.joined {
+			client.send(&Message::Leave);
+		}
+	}
+
+	/// Sends the input of the client's own paddle to the host.
+	fn send(mut client: ResMut<Client>, input_query: Query<(&PaddleInput, &Player)>) {
+		let Some(player) = client.player else {
+			return;
+		};
+		let Some((input, _)) = input_query.iter().find(|(_, other)| **other == player) else {
+			return;
+		};
+
+		client.sequence += 1;
+		let message = Message::Input {
+			sequence: client.sequence,
+			input: *input,
+		};
+		client.send(&message);
+	}
+
+	/// Shows the host's match a few steps in the past, interpolating between the snapshots around that time, and
+	/// passes on the collisions and state changes of the snapshots it went past.
+	fn interpolate(
+		mut client: ResMut<Client>,
+		mut simulation: ResMut<Simulation>,
+		mut commands: Commands,
+		mut collision_events: EventWriter<CollisionEvent>,
+		mut next_state: ResMut<NextState<GameState>>,
+		state: Res<State<GameState>>,
+	) {
+		let Some(newest) = client.snapshots.back().map(|newest| newest.state.tick) else {
+			return;
+		};
+
+		// Advance one step at a time, but drift towards the target to absorb jitter, or jump if too far off
+		let delay = client.delay();
+		let target = newest as f32 - delay;
+		let render_tick = if (target - client.render_tick).abs() > delay * 2. {
+			target
+		} else {
+			client.render_tick + 1. + (target - client.render_tick) * 0.1
+		}
+		.clamp(0., newest as f32);
+		client.render_tick = render_tick;
+
+		let mut passed = None;
+		for snapshot in client.snapshots.iter() {
+			let tick = snapshot.state.tick;
+			if tick > client.shown_tick && tick as f32 <= render_tick {
+				for collision in &snapshot.collisions {
+					collision_events.send(*collision);
+				}
+				passed = Some((tick, snapshot.game_state, snapshot.state.phase, snapshot.state.score));
+			}
+		}
+		if let Some((tick, game_state, phase, score)) = passed {
+			client.shown_tick = tick;
+			if game_state != state.0 {
+				if let Phase::GameOver { winner } = phase {
+					commands.insert_resource(MatchResult::new(winner, &score));
+				}
+				next_state.set(game_state);
+			}
+		}
+
+		let before = client
+			.snapshots
+			.iter()
+			.map(|snapshot| &snapshot.state)
+			.rev()
+			.find(|snapshot| snapshot.tick as f32 <= render_tick)
+			.or(client.snapshots.front().map(|snapshot| &snapshot.state));
+		let after = client
+			.snapshots
+			.iter()
+			.map(|snapshot| &snapshot.state)
+			.find(|snapshot| snapshot.tick as f32 >= render_tick);
+		let (Some(before), Some(after)) = (before, after) else {
+			return;
+		};
+
+		let mut state = before.clone();
+		if after.tick > before.tick {
+			let steps = (after.tick - before.tick) as f32;
+			let t = (render_tick - before.tick as f32) / steps;
+
+			// Don't smear the ball across the field when it went back to the centre
+			let settings = &simulation.settings;
+			let reach =
+				settings.physics.max_speed.max(settings.config.paddle_speed) * settings.config.time_step * steps;
+			if before.ball.position.distance(after.ball.position) <= reach {
+				state.ball.position = before.ball.position.lerp(after.ball.position, t);
+			}
+			for (paddle, target) in state.paddles.iter_mut().zip(after.paddles) {
+				paddle.y += (target.y - paddle.y) * t;
+			}
+		}
+
+		simulation.state = state;
+	}
+}
@@ -0,0 +1,253 @@
+use std::net::SocketAddr;
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+	ai_paddle::AiDifficulty,
+	ball::CollisionEvent,
+	controller::{Controller, Controllers},
+	paddle::PaddleInput,
+	player::Player,
+	reset::Reset,
+	simulation::Simulation,
+	GameSet, GameState,
+};
+
+use super::{Message, NetPlugin, Snapshot, Socket, CLIENT_PLAYER, TIMEOUT};
+
+/// A client playing on a [`Host`].
+#[derive(Debug)]
+struct Peer {
+	address: SocketAddr,
+	player: Player,
+	/// Seconds since startup when the client was last heard of.
+	last_heard: f64,
+	/// The newest [`Message::Input`] applied, older ones arriving late are dropped.
+	last_sequence: u64,
+}
+
+/// A client watching a [`Host`]'s match.
+#[derive(Debug)]
+struct Spectator {
+	address: SocketAddr,
+	/// Seconds since startup when the spectator last sent a [`Message::Watch`].
+	last_heard: f64,
+}
+
+/// Runs the match for its clients and any number of spectators, see [`super::NetMode::Host`] and
+/// [`super::NetMode::Server`].
+#[derive(Resource)]
+pub struct Host {
+	socket: Socket,
+	/// The sides played by clients, a match starts once all of them are taken.
+	seats: Vec<Player>,
+	peers: Vec<Peer>,
+	spectators: Vec<Spectator>,
+}
+
+impl Host {
+	/// A host playing against a single client.
+	pub fn new(socket: Socket) -> Self {
+		Self {
+			socket,
+			seats: vec![CLIENT_PLAYER],
+			peers: Vec::new(),
+			spectators: Vec::new(),
+		}
+	}
+
+	/// A host without a player of its own, for two clients to play on.
+	pub fn dedicated(socket: Socket) -> Self {
+		Self {
+			seats: Player::ALL.to_vec(),
+			..Self::new(socket)
+		}
+	}
+
+	/// Whether every seat is taken.
+	pub fn is_full(&self) -> bool {
+		self.peers.len() == self.seats.len()
+	}
+
+	/// The address of the client playing `player`, if any.
+	pub fn address(&self, player: Player) -> Option<SocketAddr> {
+		self.peers
+			.iter()
+			.find(|peer| peer.player == player)
+			.map(|peer| peer.address)
+	}
+
+	fn free_seat(&self) -> Option<Player> {
+		self.seats
+			.iter()
+			.copied()
+			.find(|seat| self.peers.iter().all(|peer| peer.player != *seat))
+	}
+
+	/// Sends `message` to every client and spectator.
+	fn send_to_all(&mut self, message: &Message) {
+		let addresses = self.peers.iter().map(|peer| peer.address);
+		for address in addresses.chain(self.spectators.iter().map(|spectator| spectator.address)) {
+			self.socket.send(message, address);
+		}
+	}
+}
+
+pub struct HostPlugin;
+impl Plugin for HostPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(
+			(Self::receive, Self::check_timeout)
+				.chain()
+				.distributive_run_if(NetPlugin::is_host),
+		)
+		.add_system(
+			Self::send
+				.after(GameSet::Movement)
+				.run_if(NetPlugin::is_host)
+				.in_schedule(CoreSchedule::FixedUpdate),
+		)
+		.add_system(Self::leave.in_base_set(CoreSet::Last).run_if(NetPlugin::is_host));
+	}
+}
+
+impl HostPlugin {
+	fn receive(
+		mut host: ResMut<Host>,
+		mut controllers: ResMut<Controllers>,
+		mut input_query: Query<(&mut PaddleInput, &Player, &Controller)>,
+		mut reset_writer: EventWriter<Reset>,
+		mut next_state: ResMut<NextState<GameState>>,
+		time: Res<Time>,
+	) {
+		let now = time.elapsed_seconds_f64();
+
+		for (message, from) in host.socket.receive() {
+			let peer = host.peers.iter().position(|peer| peer.address == from);
+
+			match (message, peer) {
+				// The welcome got lost
+				(Message::Join, Some(index)) => {
+					let player = host.peers[index].player;
+					host.socket.send(&Message::Welcome { player }, from);
+				}
+				(Message::Join, None) => {
+					let Some(player) = host.free_seat() else {
+						host.socket.send(&Message::Full, from);
+						continue;
+					};
+
+					info!("{} joined as {:?}", from, player);
+					host.peers.push(Peer {
+						address: from,
+						player,
+						last_heard: now,
+						last_sequence: 0,
+					});
+					host.socket.send(&Message::Welcome { player }, from);
+					controllers.set(player, Controller::Remote);
+
+					if host.is_full() {
+						reset_writer.send(Reset::Hard);
+						next_state.set(GameState::WaitBeforeRound);
+					}
+				}
+				(Message::Input { sequence, input }, Some(index)) => {
+					let peer = &mut host.peers[index];
+					peer.last_heard = now;
+					if sequence <= peer.last_sequence {
+						continue;
+					}
+					peer.last_sequence = sequence;
+
+					let peer_player = peer.player;
+					for (mut paddle_input, player, controller) in input_query.iter_mut() {
+						if *player == peer_player && *controller == Controller::Remote {
+							*paddle_input = input;
+						}
+					}
+				}
+				(Message::Leave, Some(index)) => {
+					info!("{} left", from);
+					Self::drop_peer(&mut host, index, &mut controllers, &mut reset_writer, &mut next_state);
+				}
+				(Message::Watch, _) => {
+					match host.spectators.iter_mut().find(|spectator| spectator.address == from) {
+						Some(spectator) => spectator.last_heard = now,
+						None => {
+							info!("{} is watching", from);
+							host.spectators.push(Spectator {
+								address: from,
+								last_heard: now,
+							});
+						}
+					}
+					// Also answers spectators whose first answer got lost
+					host.socket.send(&Message::Watching, from);
+				}
+				(Message::Leave, None) => {
+					host.spectators.retain(|spectator| spectator.address != from);
+				}
+				_ => {}
+			}
+		}
+	}
+
+	fn check_timeout(
+		mut host: ResMut<Host>,
+		mut controllers: ResMut<Controllers>,
+		mut reset_writer: EventWriter<Reset>,
+		mut next_state: ResMut<NextState<GameState>>,
+		time: Res<Time>,
+	) {
+		let now = time.elapsed_seconds_f64();
+		host.spectators
+			.retain(|spectator| now - spectator.last_heard <= TIMEOUT);
+
+		while let Some(index) = host.peers.iter().position(|peer| now - peer.last_heard > TIMEOUT) {
+			warn!("Lost the connection to {}", host.peers[index].address);
+			Self::drop_peer(&mut host, index, &mut controllers, &mut reset_writer, &mut next_state);
+		}
+	}
+
+	/// Hands the client's paddle to a bot and ends the match.
+	fn drop_peer(
+		host: &mut Host,
+		index: usize,
+		controllers: &mut Controllers,
+		reset_writer: &mut EventWriter<Reset>,
+		next_state: &mut NextState<GameState>,
+	) {
+		let peer = host.peers.remove(index);
+		controllers.set(peer.player, Controller::Ai(AiDifficulty::default()));
+		reset_writer.send(Reset::Hard);
+		next_state.set(GameState::Menu);
+	}
+
+	/// Sends the clients and spectators the match as it is after this step.
+	fn send(
+		mut host: ResMut<Host>,
+		simulation: Res<Simulation>,
+		state: Res<State<GameState>>,
+		mut collision_events: EventReader<CollisionEvent>,
+	) {
+		let collisions = collision_events.iter().copied().collect();
+		if host.peers.is_empty() && host.spectators.is_empty() {
+			return;
+		}
+
+		let snapshot = Snapshot {
+			state: simulation.state.clone(),
+			game_state: state.0,
+			collisions,
+		};
+		host.send_to_all(&Message::Snapshot(snapshot));
+	}
+
+	/// Tells the clients and spectators when quitting.
+	fn leave(mut host: ResMut<Host>, exit_reader: EventReader<AppExit>) {
+		if !exit_reader.is_empty() {
+			host.send_to_all(&Message::Leave);
+		}
+	}
+}
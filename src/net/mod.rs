@@ -0,0 +1,255 @@
+//! Playing across two machines. On a LAN, the host runs the [`crate::simulation::Simulation`] and is the
+//! only one to [`crate::reset::Reset`] it, the client sends its paddle's input and shows what the host sends back.
+//! Spectators are clients without a paddle.
+//! Online, two [`Rollback`] peers both run the simulation and only exchange their inputs.
+
+use std::{io, net::SocketAddr};
+
+use bevy::prelude::*;
+use bevy_turborand::*;
+
+use crate::player::Player;
+
+mod client;
+mod host;
+mod protocol;
+mod rollback;
+#[cfg(feature = "websocket")]
+mod websocket;
+
+pub use client::{Client, ClientPlugin};
+pub use host::{Host, HostPlugin};
+pub use protocol::{Message, NetworkConditions, Snapshot, Socket};
+pub use rollback::{Rollback, RollbackPlugin};
+#[cfg(feature = "websocket")]
+pub use websocket::WebSocketListener;
+
+/// Seconds without a message before the other side counts as gone.
+const TIMEOUT: f64 = 5.;
+/// The side the client plays on.
+const CLIENT_PLAYER: Player = Player::Right;
+const DEFAULT_PORT: u16 = 7878;
+
+/// What this instance does on the network.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Resource)]
+pub enum NetMode {
+	#[default]
+	Offline,
+	/// Runs the match and lets a client join on the given address.
+	Host(SocketAddr),
+	/// Runs matches between two clients joining on the given address, see the `pong-server` binary.
+	Server(SocketAddr),
+	/// Joins the match hosted at the given address.
+	Join(SocketAddr),
+	/// Watches the match hosted at the given address, without playing.
+	Watch(SocketAddr),
+	/// Plays `player` against a [`Rollback`] peer at `peer`, listening on `bind`.
+	Rollback {
+		bind: SocketAddr,
+		peer: SocketAddr,
+		player: Player,
+	},
+}
+
+impl NetMode {
+	/// Reads `PONG_HOST`, `PONG_JOIN`, `PONG_WATCH` or `PONG_PEER`, e.g. `PONG_HOST=0.0.0.0:7878` and
+	/// `PONG_JOIN=127.0.0.1:7878`.
+	/// A rollback peer also reads `PONG_BIND` (`0.0.0.0:7878` by default) and `PONG_SIDE` (`left` or `right`).
+	pub fn from_env() -> Self {
+		if let Some(address) = env("PONG_HOST") {
+			NetMode::Host(address)
+		} else if let Some(address) = env("PONG_JOIN") {
+			NetMode::Join(address)
+		} else if let Some(address) = env("PONG_WATCH") {
+			NetMode::Watch(address)
+		} else if let Some(peer) = env("PONG_PEER") {
+			NetMode::Rollback {
+				bind: env("PONG_BIND").unwrap_or_else(|| ([0, 0, 0, 0], DEFAULT_PORT).into()),
+				peer,
+				player: env("PONG_SIDE").unwrap_or(Player::Left),
+			}
+		} else {
+			NetMode::Offline
+		}
+	}
+}
+
+/// Tuning for the [`NetMode`], mostly to try the game on a bad network without having one.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct NetSettings {
+	/// Steps between reading a rollback peer's input and using it. A larger delay means fewer wrong
+	/// predictions to correct, but a less responsive paddle.
+	pub input_delay: u64,
+	pub conditions: NetworkConditions,
+	/// Where a [`NetMode::Server`] also accepts WebSocket connections. Needs the `websocket` feature.
+	pub websocket: Option<SocketAddr>,
+}
+
+impl Default for NetSettings {
+	fn default() -> Self {
+		Self {
+			input_delay: 2,
+			conditions: NetworkConditions::default(),
+			websocket: None,
+		}
+	}
+}
+
+impl NetSettings {
+	/// Reads `PONG_INPUT_DELAY` in steps, `PONG_LATENCY` and `PONG_JITTER` in milliseconds and `PONG_LOSS` as a
+	/// fraction, e.g. `PONG_LATENCY=80 PONG_JITTER=20 PONG_LOSS=0.05`.
+	pub fn from_env() -> Self {
+		let default = Self::default();
+		let milliseconds = |variable| env::<f32>(variable).map_or(0., |milliseconds| milliseconds / 1000.);
+		Self {
+			input_delay: env("PONG_INPUT_DELAY").unwrap_or(default.input_delay),
+			conditions: NetworkConditions {
+				latency: milliseconds("PONG_LATENCY"),
+				jitter: milliseconds("PONG_JITTER"),
+				loss: env("PONG_LOSS").unwrap_or(0.),
+			},
+			..default
+		}
+	}
+}
+
+/// Parses the environment variable `variable`, warning about values that don't parse.
+fn env<T>(variable: &str) -> Option<T>
+where
+	T: std::str::FromStr,
+	T::Err: std::fmt::Display,
+{
+	let value = std::env::var(variable).ok()?;
+	match value.parse() {
+		Ok(parsed) => Some(parsed),
+		Err(error) => {
+			warn!("Ignoring {}={}: {}", variable, value, error);
+			None
+		}
+	}
+}
+
+pub struct NetPlugin;
+impl Plugin for NetPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<NetMode>()
+			.init_resource::<NetSettings>()
+			.add_startup_system(Self::setup)
+			.add_plugin(HostPlugin)
+			.add_plugin(ClientPlugin)
+			.add_plugin(RollbackPlugin);
+	}
+}
+
+impl NetPlugin {
+	fn setup(
+		mut commands: Commands,
+		mut mode: ResMut<NetMode>,
+		mut rng: ResMut<GlobalRng>,
+		settings: Res<NetSettings>,
+	) {
+		let mut open = |address: SocketAddr| {
+			Socket::bind(address).map(|socket| socket.with_conditions(settings.conditions, rng.u64(..)))
+		};
+
+		let current = *mode;
+		match current {
+			NetMode::Offline => {}
+			NetMode::Host(address) => match open(address) {
+				Ok(socket) => {
+					info!("Hosting on {}", address);
+					commands.insert_resource(Host::new(socket));
+				}
+				Err(error) => {
+					error!("Failed to host on {}: {}", address, error);
+					*mode = NetMode::Offline;
+				}
+			},
+			NetMode::Server(address) => {
+				match open(address).and_then(|socket| Self::accept_websockets(socket, &settings)) {
+					Ok(socket) => {
+						info!("Serving matches on {}", address);
+						commands.insert_resource(Host::dedicated(socket));
+					}
+					Err(error) => {
+						error!("Failed to serve on {}: {}", address, error);
+						*mode = NetMode::Offline;
+					}
+				}
+			}
+			NetMode::Join(address) => match open(([0, 0, 0, 0], 0).into()) {
+				Ok(socket) => {
+					info!("Joining {}", address);
+					commands.insert_resource(Client::new(socket, address));
+				}
+				Err(error) => {
+					error!("Failed to open a socket to join {}: {}", address, error);
+					*mode = NetMode::Offline;
+				}
+			},
+			NetMode::Watch(address) => match open(([0, 0, 0, 0], 0).into()) {
+				Ok(socket) => {
+					info!("Watching {}", address);
+					commands.insert_resource(Client::spectator(socket, address));
+				}
+				Err(error) => {
+					error!("Failed to open a socket to watch {}: {}", address, error);
+					*mode = NetMode::Offline;
+				}
+			},
+			NetMode::Rollback {
+				bind: address,
+				peer,
+				player,
+			} => match open(address) {
+				Ok(socket) => {
+					info!("Playing {:?} against {} from {}", player, peer, address);
+					commands.insert_resource(Rollback::new(socket, peer, player, settings.input_delay));
+				}
+				Err(error) => {
+					error!("Failed to listen on {}: {}", address, error);
+					*mode = NetMode::Offline;
+				}
+			},
+		}
+	}
+
+	/// Lets `socket` also take WebSocket connections, see [`NetSettings::websocket`].
+	#[cfg(feature = "websocket")]
+	fn accept_websockets(socket: Socket, settings: &NetSettings) -> io::Result<Socket> {
+		let Some(address) = settings.websocket else {
+			return Ok(socket);
+		};
+		let listener = WebSocketListener::bind(address)?;
+		info!("Accepting WebSocket connections on {}", address);
+		Ok(socket.with_websocket(listener))
+	}
+
+	#[cfg(not(feature = "websocket"))]
+	fn accept_websockets(socket: Socket, settings: &NetSettings) -> io::Result<Socket> {
+		if let Some(address) = settings.websocket {
+			warn!(
+				"Not listening for WebSockets on {}, this build lacks the `websocket` feature",
+				address
+			);
+		}
+		Ok(socket)
+	}
+
+	pub fn is_host(host: Option<Res<Host>>) -> bool {
+		host.is_some()
+	}
+
+	pub fn is_client(client: Option<Res<Client>>) -> bool {
+		client.is_some()
+	}
+
+	pub fn is_rollback(rollback: Option<Res<Rollback>>) -> bool {
+		rollback.is_some()
+	}
+
+	/// Whether the [`crate::simulation::SimulationPlugin`] runs the match, rather than a host or a rollback session.
+	pub fn is_authoritative(client: Option<Res<Client>>, rollback: Option<Res<Rollback>>) -> bool {
+		client.is_none() && rollback.is_none()
+	}
+}
@@ -0,0 +1,210 @@
+use std::{
+	io,
+	net::{SocketAddr, ToSocketAddrs, UdpSocket},
+	time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	ball::CollisionEvent,
+	paddle::PaddleInput,
+	player::Player,
+	simulation::{SimRng, SimSettings, SimState},
+	GameState,
+};
+
+/// Large enough for any [`Message`], and for any UDP datagram.
+const MAX_PACKET_SIZE: usize = 65_536;
+
+/// Everything sent between a host and its client, or between two rollback peers.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Message {
+	/// Asks the host to play. Repeated until the host answers.
+	Join,
+	/// The host accepted the client, who plays `player`.
+	Welcome { player: Player },
+	/// The host already has a client.
+	Full,
+	/// Asks the host to watch. Repeated every second while watching, so the host knows the spectator is still there.
+	Watch,
+	/// The host accepted the spectator.
+	Watching,
+	/// The client's input, `sequence` counts up with every step.
+	Input { sequence: u64, input: PaddleInput },
+	/// The host's match after a step.
+	Snapshot(Snapshot),
+	/// Sent when quitting, so the other side doesn't have to wait for a timeout.
+	Leave,
+	/// The left rollback peer proposes a match. Repeated until the right one answers with [`Message::Ready`].
+	Start { seed: u64, settings: SimSettings },
+	/// The right rollback peer started the match with `seed`.
+	Ready { seed: u64 },
+	/// The right rollback peer asks for a new match.
+	Rematch,
+	/// A rollback peer's inputs for the steps from `first` on, and how many of the other peer's inputs it has.
+	/// Everything the other peer didn't confirm yet is sent again, so a lost packet costs nothing but time.
+	Inputs {
+		seed: u64,
+		first: u64,
+		inputs: Vec<PaddleInput>,
+		received: u64,
+	},
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Snapshot {
+	pub state: SimState,
+	pub game_state: GameState,
+	/// Collisions of the step that led to `state`.
+	pub collisions: Vec<CollisionEvent>,
+}
+
+/// A bad network to try the game on without one, applied to every packet a [`Socket`] sends.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NetworkConditions {
+	/// Seconds every packet is held back.
+	pub latency: f32,
+	/// Seconds the latency varies by in either direction, which also reorders packets.
+	pub jitter: f32,
+	/// Fraction of packets dropped, from `0.` to `1.`.
+	pub loss: f32,
+}
+
+impl NetworkConditions {
+	pub fn is_perfect(&self) -> bool {
+		self.latency <= 0. && self.jitter <= 0. && self.loss <= 0.
+	}
+}
+
+/// A packet held back by [`NetworkConditions`].
+struct DelayedPacket {
+	due: Instant,
+	bytes: Vec<u8>,
+	to: SocketAddr,
+}
+
+/// A non-blocking UDP socket sending and receiving [`Message`]s.
+pub struct Socket {
+	socket: UdpSocket,
+	conditions: NetworkConditions,
+	rng: SimRng,
+	delayed: Vec<DelayedPacket>,
+	#[cfg(feature = "websocket")]
+	websocket: Option<super::WebSocketListener>,
+}
+
+impl Socket {
+	pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+		let socket = UdpSocket::bind(address)?;
+		socket.set_nonblocking(true)?;
+		Ok(Self {
+			socket,
+			conditions: NetworkConditions::default(),
+			rng: SimRng::new(0),
+			delayed: Vec::new(),
+			#[cfg(feature = "websocket")]
+			websocket: None,
+		})
+	}
+
+	/// Simulates `conditions` on every packet sent, with `seed` deciding which ones get lost.
+	pub fn with_conditions(mut self, conditions: NetworkConditions, seed: u64) -> Self {
+		self.conditions = conditions;
+		self.rng = SimRng::new(seed);
+		self
+	}
+
+	/// Also sends and receives through the connections of `listener`, as if they were UDP peers.
+	#[cfg(feature = "websocket")]
+	pub fn with_websocket(mut self, listener: super::WebSocketListener) -> Self {
+		self.websocket = Some(listener);
+		self
+	}
+
+	pub fn local_addr(&self) -> io::Result<SocketAddr> {
+		self.socket.local_addr()
+	}
+
+	/// Sends `message` to `to`. Delivery isn't guaranteed, so failures are only logged.
+	pub fn send(&mut self, message: &Message, to: SocketAddr) {
+		let bytes = match serde_json::to_vec(message) {
+			Ok(bytes) => bytes,
+			Err(error) => {
+				error!("Failed to encode {:?}: {}", message, error);
+				return;
+			}
+		};
+
+		#[cfg(feature = "websocket")]
+		if let Some(websocket) = &self.websocket {
+			if websocket.send(&String::from_utf8_lossy(&bytes), to) {
+				return;
+			}
+		}
+
+		if self.conditions.is_perfect() {
+			self.send_bytes(&bytes, to);
+			return;
+		}
+
+		if self.rng.f32() < self.conditions.loss {
+			return;
+		}
+		let delay = self.conditions.latency + self.conditions.jitter * self.rng.f32_normalized();
+		self.delayed.push(DelayedPacket {
+			due: Instant::now() + Duration::from_secs_f32(delay.max(0.)),
+			bytes,
+			to,
+		});
+		self.flush();
+	}
+
+	fn send_bytes(&self, bytes: &[u8], to: SocketAddr) {
+		if let Err(error) = self.socket.send_to(bytes, to) {
+			debug!("Failed to send to {}: {}", to, error);
+		}
+	}
+
+	/// Sends the packets held back by [`NetworkConditions`] that are due.
+	fn flush(&mut self) {
+		let now = Instant::now();
+		let (mut due, delayed): (Vec<_>, Vec<_>) = self.delayed.drain(..).partition(|packet| packet.due <= now);
+		self.delayed = delayed;
+
+		due.sort_by_key(|packet| packet.due);
+		for packet in due {
+			self.send_bytes(&packet.bytes, packet.to);
+		}
+	}
+
+	/// Every message received since the last call, skipping malformed ones.
+	pub fn receive(&mut self) -> Vec<(Message, SocketAddr)> {
+		self.flush();
+
+		let mut buffer = vec![0; MAX_PACKET_SIZE];
+		let mut messages = Vec::new();
+
+		loop {
+			match self.socket.recv_from(&mut buffer) {
+				Ok((length, from)) => match serde_json::from_slice(&buffer[..length]) {
+					Ok(message) => messages.push((message, from)),
+					Err(error) => debug!("Dropping malformed packet from {}: {}", from, error),
+				},
+				Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+				Err(error) => {
+					debug!("Failed to receive: {}", error);
+					break;
+				}
+			}
+		}
+
+		#[cfg(feature = "websocket")]
+		if let Some(websocket) = &self.websocket {
+			messages.extend(websocket.receive());
+		}
+
+		messages
+	}
+}
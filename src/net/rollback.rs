@@ -0,0 +1,481 @@
+use std::{collections::VecDeque, net::SocketAddr};
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_turborand::*;
+
+use crate::{
+	ball::{BallPhysics, CollisionEvent},
+	config::GameConfig,
+	controller::{Controller, Controllers},
+	paddle::PaddleInput,
+	player::Player,
+	reset::Reset,
+	rules::{MatchResult, MatchRules},
+	simulation::{Phase, SimEvent, SimSettings, SimState, Simulation},
+	GameSet, GameState,
+};
+
+use super::{Message, NetPlugin, Socket, TIMEOUT};
+
+/// Steps a peer may run ahead of the last input it has from the other one. It waits for the other peer beyond that.
+const MAX_PREDICTION: u64 = 8;
+/// Seconds between repeated [`Message::Start`]s and [`Message::Rematch`]es.
+const RETRY_INTERVAL: f64 = 0.5;
+/// Inputs sent per [`Message::Inputs`] at most.
+const MAX_INPUTS_PER_PACKET: usize = 64;
+
+/// A step that was simulated without knowing the other peer's input.
+struct Frame {
+	/// The state before the step.
+	state: SimState,
+	/// The other peer's input the step was simulated with.
+	remote: PaddleInput,
+}
+
+/// Plays a match against a peer, see [`super::NetMode::Rollback`].
+///
+/// Both peers simulate the whole match. The other peer's input is predicted until it arrives, and when the
+/// prediction was wrong, the steps since are simulated again from the state kept before them.
+#[derive(Resource)]
+pub struct Rollback {
+	socket: Socket,
+	peer: SocketAddr,
+	/// The side played on this machine. [`Player::Left`] proposes the matches.
+	player: Player,
+	/// Steps between reading the local input and using it, see [`super::NetSettings::input_delay`].
+	input_delay: u64,
+	/// The seed of the match being played, `None` until both peers agreed on one.
+	seed: Option<u64>,
+	/// The seed of a match proposed by the left peer, or asked for by the right one.
+	pending: Option<u64>,
+	/// Seconds since startup when the other peer was last heard of.
+	last_heard: f64,
+	/// Seconds since startup when the last [`Message::Start`] or [`Message::Rematch`] was sent.
+	last_retry: f64,
+	/// The local input of every step of the match, including the ones still delayed.
+	local: Vec<PaddleInput>,
+	/// The other peer's input of every step it sent so far.
+	remote: Vec<PaddleInput>,
+	/// How many of `local` the other peer has.
+	acknowledged: u64,
+	/// The steps simulated without the other peer's input, oldest first.
+	frames: VecDeque<Frame>,
+	/// The first step whose prediction turned out wrong.
+	mispredicted: Option<u64>,
+}
+
+impl Rollback {
+	pub fn new(socket: Socket, peer: SocketAddr, player: Player, input_delay: u64) -> Self {
+		Self {
+			socket,
+			peer,
+			player,
+			input_delay,
+			seed: None,
+			pending: None,
+			last_heard: 0.,
+			last_retry: f64::NEG_INFINITY,
+			local: Vec::new(),
+			remote: Vec::new(),
+			acknowledged: 0,
+			frames: VecDeque::new(),
+			mispredicted: None,
+		}
+	}
+
+	fn send(&mut self, message: &Message) {
+		self.socket.send(message, self.peer);
+	}
+
+	fn proposes(&self) -> bool {
+		self.player == Player::Left
+	}
+
+	fn start(&mut self, seed: u64, now: f64) {
+		info!("Playing {:?} against {} with seed {}", self.player, self.peer, seed);
+		self.seed = Some(seed);
+		self.pending = None;
+		self.last_heard = now;
+		self.local = vec![PaddleInput::default(); self.input_delay as usize];
+		self.remote.clear();
+		self.acknowledged = 0;
+		self.frames.clear();
+		self.mispredicted = None;
+	}
+
+	/// The other peer's input for `tick`, predicted to stay the same as the last one known.
+	fn remote_input(&self, tick: u64) -> PaddleInput {
+		self.remote
+			.get(tick as usize)
+			.or_else(|| self.remote.last())
+			.copied()
+			.unwrap_or_default()
+	}
+
+	fn inputs(&self, tick: u64, remote: PaddleInput) -> [PaddleInput; 2] {
+		let mut inputs = [remote; 2];
+		inputs[self.player.index()] = self.local[tick as usize];
+		inputs
+	}
+
+	/// Adds the inputs of a [`Message::Inputs`], remembering the first one that was predicted wrong.
+	fn receive_inputs(&mut self, first: u64, inputs: Vec<PaddleInput>, received: u64) {
+		self.acknowledged = self.acknowledged.max(received);
+
+		for (tick, input) in (first..).zip(inputs) {
+			// Everything not acknowledged is sent again, so only the next missing input is of interest
+			if tick != self.remote.len() as u64 {
+				continue;
+			}
+
+			if let Some(frame) = self.frame(tick) {
+				if frame.remote != input {
+					self.mispredicted = Some(self.mispredicted.map_or(tick, |mispredicted| mispredicted.min(tick)));
+				}
+			}
+			self.remote.push(input);
+		}
+	}
+
+	fn frame(&self, tick: u64) -> Option<&Frame> {
+		let oldest = self.frames.front()?.state.tick;
+		self.frames.get(tick.checked_sub(oldest)? as usize)
+	}
+
+	/// Simulates the steps since a wrong prediction again, with the inputs known now.
+	fn resimulate(&mut self, simulation: &mut Simulation) {
+		let Some(tick) = self.mispredicted.take() else {
+			return;
+		};
+		let Some(oldest) = self.frames.front().map(|frame| frame.state.tick) else {
+			return;
+		};
+
+		let mut state = self.frames[(tick - oldest) as usize].state.clone();
+		for index in (tick - oldest) as usize..self.frames.len() {
+			let remote = self.remote_input(state.tick);
+			let inputs = self.inputs(state.tick, remote);
+			self.frames[index] = Frame {
+				state: state.clone(),
+				remote,
+			};
+			// Sounds were already played the first time
+			state.step(&simulation.settings, &inputs);
+		}
+		simulation.state = state;
+	}
+
+	/// Simulates the next step with `local` and the other peer's input as far as it's known, keeping the state before.
+	fn predict(&mut self, simulation: &mut Simulation, local: PaddleInput) -> Vec<SimEvent> {
+		let tick = simulation.state.tick;
+		self.local.push(local);
+
+		let remote = self.remote_input(tick);
+		let inputs = self.inputs(tick, remote);
+		self.frames.push_back(Frame {
+			state: simulation.state.clone(),
+			remote,
+		});
+		simulation.step(&inputs)
+	}
+
+	/// Forgets the steps whose inputs are all known, they can't be predicted wrong anymore.
+	fn confirm(&mut self) {
+		let confirmed = self.remote.len() as u64;
+		while self.frames.front().map_or(false, |frame| frame.state.tick < confirmed) {
+			self.frames.pop_front();
+		}
+	}
+
+	/// Sends every local input the other peer doesn't have yet.
+	fn send_inputs(&mut self, seed: u64) {
+		let first = self.acknowledged.min(self.local.len() as u64);
+		let inputs = self.local[first as usize..]
+			.iter()
+			.take(MAX_INPUTS_PER_PACKET)
+			.copied()
+			.collect();
+		let message = Message::Inputs {
+			seed,
+			first,
+			inputs,
+			received: self.remote.len() as u64,
+		};
+		self.send(&message);
+	}
+}
+
+pub struct RollbackPlugin;
+impl Plugin for RollbackPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(Self::connect.run_if(NetPlugin::is_rollback))
+			.add_system(
+				Self::advance
+					.in_set(GameSet::Movement)
+					.run_if(NetPlugin::is_rollback)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			)
+			.add_system(Self::leave.in_base_set(CoreSet::Last).run_if(NetPlugin::is_rollback));
+	}
+}
+
+impl RollbackPlugin {
+	/// Agrees on matches with the other peer and collects its inputs.
+	#[allow(clippy::too_many_arguments)]
+	fn connect(
+		mut rollback: ResMut<Rollback>,
+		mut simulation: ResMut<Simulation>,
+		mut controllers: ResMut<Controllers>,
+		mut rng: ResMut<GlobalRng>,
+		mut reset_reader: EventReader<Reset>,
+		mut next_state: ResMut<NextState<GameState>>,
+		rules: Res<MatchRules>,
+		physics: Res<BallPhysics>,
+		config: Res<GameConfig>,
+		time: Res<Time>,
+	) {
+		let now = time.elapsed_seconds_f64();
+		let peer = rollback.peer;
+
+		for (message, from) in rollback.socket.receive() {
+			if from != peer {
+				continue;
+			}
+			rollback.last_heard = now;
+
+			match message {
+				Message::Start { seed, settings } if !rollback.proposes() => {
+					if rollback.seed != Some(seed) {
+						*simulation = Simulation::new(settings, seed, false);
+						rollback.start(seed, now);
+						Self::assign_controllers(&rollback, &mut controllers);
+						next_state.set(simulation.state.phase.game_state());
+					}
+					// Also answers repeated proposals, in case the answer got lost
+					rollback.send(&Message::Ready { seed });
+				}
+				Message::Ready { seed } if rollback.proposes() && rollback.pending == Some(seed) => {
+					rollback.start(seed, now);
+					Self::assign_controllers(&rollback, &mut controllers);
+					next_state.set(simulation.state.phase.game_state());
+				}
+				Message::Rematch if rollback.proposes() && rollback.pending.is_none() => {
+					rollback.pending = Some(rng.u64(..));
+				}
+				Message::Inputs {
+					seed,
+					first,
+					inputs,
+					received,
+				} if rollback.seed == Some(seed) => rollback.receive_inputs(first, inputs, received),
+				Message::Leave => {
+					warn!("{} left", from);
+					Self::end(&mut rollback, &mut controllers, &mut next_state);
+				}
+				_ => {}
+			}
+		}
+
+		if rollback.seed.is_some() && now - rollback.last_heard > TIMEOUT {
+			warn!("Lost the connection to {}", peer);
+			Self::end(&mut rollback, &mut controllers, &mut next_state);
+		}
+
+		// Either peer restarting the match restarts it for both
+		if reset_reader.iter().any(|reset| *reset == Reset::Hard) {
+			rollback.pending = Some(rng.u64(..));
+		}
+		if rollback.proposes() && rollback.seed.is_none() && rollback.pending.is_none() {
+			rollback.pending = Some(rng.u64(..));
+		}
+
+		let Some(seed) = rollback.pending else {
+			return;
+		};
+		if rollback.proposes() && simulation.state.seed != seed {
+			let settings = SimSettings {
+				rules: rules.clone(),
+				physics: physics.clone(),
+				config: config.clone(),
+			};
+			*simulation = Simulation::new(settings, seed, false);
+			// Nothing is simulated until the other peer confirmed the match
+			rollback.seed = None;
+		}
+		if now - rollback.last_retry > RETRY_INTERVAL {
+			rollback.last_retry = now;
+			let message = if rollback.proposes() {
+				Message::Start {
+					seed,
+					settings: simulation.settings.clone(),
+				}
+			} else {
+				Message::Rematch
+			};
+			rollback.send(&message);
+		}
+	}
+
+	fn assign_controllers(rollback: &Rollback, controllers: &mut Controllers) {
+		controllers.set(rollback.player, Controller::Local);
+		controllers.set(rollback.player.opponent(), Controller::Remote);
+	}
+
+	/// Stops the match and waits for the other peer to come back.
+	fn end(rollback: &mut Rollback, controllers: &mut Controllers, next_state: &mut NextState<GameState>) {
+		rollback.seed = None;
+		rollback.pending = None;
+		*controllers = Controllers::default();
+		next_state.set(GameState::Menu);
+	}
+
+	/// Corrects wrong predictions and simulates the next step, unless too far ahead of the other peer.
+	#[allow(clippy::too_many_arguments)]
+	fn advance(
+		mut rollback: ResMut<Rollback>,
+		mut simulation: ResMut<Simulation>,
+		mut commands: Commands,
+		mut collision_events: EventWriter<CollisionEvent>,
+		mut reset_writer: EventWriter<Reset>,
+		mut next_state: ResMut<NextState<GameState>>,
+		input_query: Query<(&PaddleInput, &Player)>,
+		state: Res<State<GameState>>,
+	) {
+		let Some(seed) = rollback.seed else {
+			return;
+		};
+		let phase = simulation.state.phase;
+
+		rollback.resimulate(&mut simulation);
+
+		let tick = simulation.state.tick;
+		let playing = matches!(
+			state.0,
+			GameState::WaitBeforeRound | GameState::Serve | GameState::Playing
+		);
+		if playing && tick < rollback.remote.len() as u64 + MAX_PREDICTION {
+			let local = input_query
+				.iter()
+				.find(|(_, player)| **player == rollback.player)
+				.map(|(input, _)| *input)
+				.unwrap_or_default();
+			for event in rollback.predict(&mut simulation, local) {
+				match event {
+					SimEvent::Collision(collision) => collision_events.send(collision),
+					SimEvent::BallReset => reset_writer.send(Reset::Soft),
+					SimEvent::PhaseChanged(_) => {}
+				}
+			}
+		}
+
+		// A correction may change the phase as well, e.g. take back a goal that ended the match
+		let new_phase = simulation.state.phase;
+		if new_phase.game_state() != phase.game_state() {
+			if let Phase::GameOver { winner } = new_phase {
+				commands.insert_resource(MatchResult::new(winner, &simulation.state.score));
+			}
+			next_state.set(new_phase.game_state());
+		}
+
+		rollback.confirm();
+		rollback.send_inputs(seed);
+	}
+
+	/// Tells the other peer when quitting.
+	fn leave(mut rollback: ResMut<Rollback>, exit_reader: EventReader<AppExit>) {
+		if !exit_reader.is_empty() {
+			rollback.send(&Message::Leave);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SEED: u64 = 3;
+
+	fn input(axis: f32) -> PaddleInput {
+		PaddleInput { axis, serve: true }
+	}
+
+	/// A match against a peer that hasn't sent anything yet, playing the left paddle without input delay.
+	fn rollback() -> (Rollback, Simulation) {
+		let socket = Socket::bind("127.0.0.1:0").expect("a local socket should be free");
+		let peer = socket.local_addr().expect("the socket should have an address");
+		let mut rollback = Rollback::new(socket, peer, Player::Left, 0);
+		rollback.start(SEED, 0.);
+		(rollback, Simulation::new(SimSettings::default(), SEED, false))
+	}
+
+	/// The match played with every input known from the start.
+	fn straight_run(local: &[PaddleInput], remote: &[PaddleInput]) -> SimState {
+		let mut simulation = Simulation::new(SimSettings::default(), SEED, false);
+		for (local, remote) in local.iter().zip(remote) {
+			simulation.step(&[*local, *remote]);
+		}
+		simulation.state
+	}
+
+	#[test]
+	fn late_inputs_correct_the_prediction() {
+		let (mut rollback, mut simulation) = rollback();
+		let local = [input(0.5); 10];
+		let mut remote = [PaddleInput::default(); 10];
+		remote[3..].fill(input(-1.));
+
+		for step in local {
+			rollback.predict(&mut simulation, step);
+		}
+		assert_ne!(simulation.state, straight_run(&local, &remote));
+
+		rollback.receive_inputs(0, remote.to_vec(), 10);
+		assert_eq!(rollback.mispredicted, Some(3));
+		rollback.resimulate(&mut simulation);
+
+		assert_eq!(simulation.state, straight_run(&local, &remote));
+		assert_eq!(rollback.mispredicted, None);
+	}
+
+	#[test]
+	fn right_predictions_are_not_simulated_again() {
+		let (mut rollback, mut simulation) = rollback();
+		rollback.receive_inputs(0, vec![input(1.)], 0);
+
+		for _ in 0..6 {
+			rollback.predict(&mut simulation, input(0.));
+		}
+		let predicted = simulation.state.clone();
+		// The other peer kept its paddle moving, as predicted
+		rollback.receive_inputs(1, vec![input(1.); 5], 6);
+
+		assert_eq!(rollback.mispredicted, None);
+		rollback.resimulate(&mut simulation);
+		assert_eq!(simulation.state, predicted);
+	}
+
+	#[test]
+	fn frames_are_kept_until_their_inputs_are_known() {
+		let (mut rollback, mut simulation) = rollback();
+		for _ in 0..10 {
+			rollback.predict(&mut simulation, input(0.));
+		}
+
+		rollback.confirm();
+		assert_eq!(rollback.frames.len(), 10);
+
+		rollback.receive_inputs(0, vec![PaddleInput::default(); 4], 10);
+		rollback.confirm();
+		assert_eq!(rollback.frames.len(), 6);
+		assert_eq!(rollback.frames.front().map(|frame| frame.state.tick), Some(4));
+
+		// Inputs out of order wait for the missing ones
+		rollback.receive_inputs(6, vec![PaddleInput::default(); 4], 10);
+		rollback.confirm();
+		assert_eq!(rollback.frames.len(), 6);
+
+		rollback.receive_inputs(4, vec![PaddleInput::default(); 6], 10);
+		rollback.confirm();
+		assert!(rollback.frames.is_empty());
+	}
+}
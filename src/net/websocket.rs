@@ -0,0 +1,143 @@
+use std::{
+	collections::HashMap,
+	io,
+	net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+	sync::{
+		mpsc::{self, Receiver, Sender},
+		Arc, Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+use bevy::prelude::*;
+use tungstenite::WebSocket;
+
+use super::Message;
+
+/// How long a connection waits for a message before looking for ones to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+type Connections = Arc<Mutex<HashMap<SocketAddr, Sender<String>>>>;
+
+/// Accepts WebSocket connections for clients that can't use UDP, like browsers. Every connection is served by a
+/// thread of its own, which hands its [`Message`]s over to the game and sends back the ones addressed to it.
+pub struct WebSocketListener {
+	incoming: Mutex<Receiver<(Message, SocketAddr)>>,
+	/// The outgoing messages of every open connection, by the address it comes from.
+	connections: Connections,
+}
+
+impl WebSocketListener {
+	pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+		let listener = TcpListener::bind(address)?;
+		let (sender, incoming) = mpsc::channel();
+		let connections = Connections::default();
+
+		let accepted = connections.clone();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				match stream {
+					Ok(stream) => {
+						let sender = sender.clone();
+						let connections = accepted.clone();
+						thread::spawn(move || serve(stream, sender, connections));
+					}
+					Err(error) => debug!("Failed to accept a WebSocket connection: {}", error),
+				}
+			}
+		});
+
+		Ok(Self {
+			incoming: Mutex::new(incoming),
+			connections,
+		})
+	}
+
+	/// Sends `json` if `to` is a WebSocket connection, returns whether it is.
+	pub fn send(&self, json: &str, to: SocketAddr) -> bool {
+		let Ok(connections) = self.connections.lock() else {
+			return false;
+		};
+		let Some(connection) = connections.get(&to) else {
+			return false;
+		};
+
+		// The connection may have closed in the meantime, which is as good as a lost packet
+		let _ = connection.send(json.to_owned());
+		true
+	}
+
+	/// Every message received since the last call.
+	pub fn receive(&self) -> Vec<(Message, SocketAddr)> {
+		match self.incoming.lock() {
+			Ok(incoming) => incoming.try_iter().collect(),
+			Err(_) => Vec::new(),
+		}
+	}
+}
+
+/// Passes messages between one WebSocket connection and the game until the connection closes.
+fn serve(stream: TcpStream, incoming: Sender<(Message, SocketAddr)>, connections: Connections) {
+	let Ok(address) = stream.peer_addr() else {
+		return;
+	};
+	let mut websocket = match tungstenite::accept(stream) {
+		Ok(websocket) => websocket,
+		Err(error) => {
+			debug!("WebSocket handshake with {} failed: {}", address, error);
+			return;
+		}
+	};
+	if let Err(error) = websocket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+		debug!("Failed to set up the WebSocket connection to {}: {}", address, error);
+		return;
+	}
+
+	let (sender, outgoing) = mpsc::channel();
+	if let Ok(mut connections) = connections.lock() {
+		connections.insert(address, sender);
+	}
+	info!("WebSocket connection from {}", address);
+
+	while exchange(&mut websocket, address, &incoming, &outgoing) {}
+
+	if let Ok(mut connections) = connections.lock() {
+		connections.remove(&address);
+	}
+	debug!("WebSocket connection from {} closed", address);
+}
+
+/// Reads at most one message and writes all pending ones, returns whether the connection is still open.
+fn exchange(
+	websocket: &mut WebSocket<TcpStream>,
+	address: SocketAddr,
+	incoming: &Sender<(Message, SocketAddr)>,
+	outgoing: &Receiver<String>,
+) -> bool {
+	let received = match websocket.read_message() {
+		Ok(tungstenite::Message::Text(text)) => serde_json::from_str(&text).ok(),
+		Ok(tungstenite::Message::Binary(bytes)) => serde_json::from_slice(&bytes).ok(),
+		Ok(tungstenite::Message::Close(_)) => return false,
+		// Pings are answered by tungstenite itself
+		Ok(_) => None,
+		Err(tungstenite::Error::Io(error))
+			if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) =>
+		{
+			None
+		}
+		Err(_) => return false,
+	};
+	if let Some(message) = received {
+		if incoming.send((message, address)).is_err() {
+			return false;
+		}
+	}
+
+	for json in outgoing.try_iter() {
+		if websocket.write_message(tungstenite::Message::Text(json)).is_err() {
+			return false;
+		}
+	}
+	true
+}
@@ -1,137 +1,142 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{collider::Collider, player::Player, reset::Reset, wall::Wall, GameSet, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::{
+	collider::{Aabb, Collider},
+	config::GameConfig,
+	controller::Controller,
+	controls::{Action, KeyBindings},
+	player::Player,
+	script::InputScript,
+	simulation::{PaddleState, Simulation},
+	GameSet, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
 
+/// Mirrors the paddle's [`crate::simulation::PaddleState`] for everything outside the simulation.
 #[derive(Debug, Default, Component)]
 pub struct Paddle {
 	pub speed: f32,
 	pub velocity: Vec2,
 }
 
+/// What the paddle's [`Controller`] asks for this step.
+#[derive(Clone, Copy, Debug, Default, Component, Deserialize, PartialEq, Serialize)]
+pub struct PaddleInput {
+	/// Vertical movement as a fraction of [`Paddle::speed`], from `-1.` to `1.`.
+	pub axis: f32,
+	/// Launches the ball while this paddle is serving.
+	pub serve: bool,
+}
+
 impl Paddle {
-	pub const WIDTH: f32 = Wall::WIDTH;
-	pub const HEIGHT: f32 = WINDOW_HEIGHT * 0.2;
-	pub const MARGIN: f32 = Wall::WIDTH * 2.0;
-	pub const SPEED: f32 = WINDOW_HEIGHT / 2.5;
-
-	pub fn new(velocity: Vec2) -> Self {
-		Self {
-			speed: Self::SPEED,
-			velocity,
+	pub fn new(speed: f32, velocity: Vec2) -> Self {
+		Self { speed, velocity }
+	}
+
+	/// The size of a paddle `height` pixels tall, it is as wide as the walls.
+	pub fn size(height: f32, config: &GameConfig) -> Vec2 {
+		Vec2::new(config.wall_width, height)
+	}
+
+	/// How far the centre of a paddle `height` pixels tall can move away from the middle of the field.
+	pub fn max_y(height: f32, config: &GameConfig) -> f32 {
+		// A power-up can grow a paddle taller than the field
+		(WINDOW_HEIGHT / 2. - height / 2. - config.wall_width).max(0.)
+	}
+
+	/// The x coordinate of the centre of `player`'s paddle.
+	pub fn x(player: Player, config: &GameConfig) -> f32 {
+		match player {
+			Player::Left => config.paddle_margin - (WINDOW_WIDTH / 2.) + config.wall_width / 2.,
+			Player::Right => (WINDOW_WIDTH / 2.) - config.paddle_margin - config.wall_width / 2.,
 		}
 	}
+
+	pub fn aabb(player: Player, paddle: &PaddleState, config: &GameConfig) -> Aabb {
+		Aabb::new(
+			Vec2::new(Self::x(player, config), paddle.y),
+			Self::size(paddle.height(config), config),
+		)
+	}
 }
 
 pub struct PaddlePlugin;
 impl Plugin for PaddlePlugin {
 	fn build(&self, app: &mut App) {
 		app.add_startup_system(Self::setup)
-			.add_system(
-				Self::handle_reset
-					.in_set(GameSet::Reset)
-					.in_schedule(CoreSchedule::FixedUpdate),
-			)
 			.add_system(
 				Self::handle_input
 					.in_set(GameSet::Input)
 					.in_schedule(CoreSchedule::FixedUpdate),
 			)
-			.add_system(
-				Self::update_position
-					.in_set(GameSet::Movement)
-					.in_schedule(CoreSchedule::FixedUpdate),
-			);
+			.add_system(Self::sync.in_set(GameSet::Sync).in_schedule(CoreSchedule::FixedUpdate));
 	}
 }
 
 impl PaddlePlugin {
-	pub fn setup(mut commands: Commands) {
-		commands.spawn((
-			Name::new("Paddle Left"),
-			Collider,
-			Paddle::new(Vec2::default()),
-			Player::Left,
-			SpriteBundle {
-				sprite: Sprite {
-					custom_size: Some(Vec2::new(Paddle::WIDTH, Paddle::HEIGHT)),
+	pub fn setup(mut commands: Commands, config: Res<GameConfig>) {
+		for player in Player::ALL {
+			commands.spawn((
+				Name::new(format!("Paddle {:?}", player)),
+				Collider,
+				Paddle::new(config.paddle_speed, Vec2::default()),
+				PaddleInput::default(),
+				player,
+				SpriteBundle {
+					sprite: Sprite {
+						custom_size: Some(Paddle::size(config.paddle_height, &config)),
+						..default()
+					},
+					transform: Transform::from_translation(Vec3::new(Paddle::x(player, &config), 0., 0.)),
 					..default()
 				},
-				transform: Transform::from_translation(Vec3::new(
-					Paddle::MARGIN - (WINDOW_WIDTH / 2.) + Wall::WIDTH / 2.,
-					0.,
-					0.,
-				)),
-				..default()
-			},
-		));
-		commands.spawn((
-			Name::new("Paddle Right"),
-			Collider,
-			Paddle::new(Vec2::default()),
-			Player::Right,
-			SpriteBundle {
-				sprite: Sprite {
-					custom_size: Some(Vec2::new(Paddle::WIDTH, Paddle::HEIGHT)),
-					..default()
-				},
-				transform: Transform::from_translation(Vec3::new(
-					(WINDOW_WIDTH / 2.) - Paddle::MARGIN - Wall::WIDTH / 2.,
-					0.,
-					0.,
-				)),
-				..default()
-			},
-		));
-	}
-
-	pub fn handle_reset(
-		mut paddle_query: Query<(&mut Sprite, &mut Transform, &mut Paddle, &Player)>,
-		mut reset_reader: EventReader<Reset>,
-	) {
-		if let Some(Reset::Hard) = reset_reader.iter().last() {
-			for (mut sprite, mut transform, mut paddle, &player) in paddle_query.iter_mut() {
-				paddle.speed = Paddle::SPEED;
-
-				sprite.custom_size = Some(Vec2::new(Paddle::WIDTH, Paddle::HEIGHT));
-
-				let translation_x = match player {
-					Player::Left => Paddle::MARGIN - (WINDOW_WIDTH / 2.) + Wall::WIDTH / 2.,
-					Player::Right => (WINDOW_WIDTH / 2.) - Paddle::MARGIN - Wall::WIDTH / 2.,
-				};
-
-				transform.translation = Vec3::new(translation_x, 0., 0.);
-			}
+			));
 		}
 	}
 
-	pub fn handle_input(keys: Res<Input<KeyCode>>, mut query: Query<(&mut Paddle, &Player)>) {
+	pub fn handle_input(
+		keys: Res<Input<KeyCode>>,
+		bindings: Res<KeyBindings>,
+		script: Res<InputScript>,
+		simulation: Res<Simulation>,
+		mut query: Query<(&mut PaddleInput, &Player, &Controller)>,
+	) {
 		//debug!("Handling input for paddles...");
-		for (mut paddle, player) in query.iter_mut() {
-			let (up_keycode, down_keycode) = player.movement_keys();
+		for (mut input, player, controller) in query.iter_mut() {
+			if *controller == Controller::Script {
+				*input = script.timeline(*player).keys(simulation.state.tick).input();
+				continue;
+			}
+			if *controller != Controller::Local {
+				continue;
+			}
 
-			if keys.pressed(up_keycode) {
-				paddle.velocity.y = paddle.speed;
-			} else if keys.pressed(down_keycode) {
-				paddle.velocity.y = -paddle.speed;
+			if bindings.pressed(&keys, Action::Up(*player)) {
+				input.axis = 1.;
+			} else if bindings.pressed(&keys, Action::Down(*player)) {
+				input.axis = -1.;
 			} else {
-				paddle.velocity.y = 0.;
+				input.axis = 0.;
 			}
+
+			input.serve = bindings.pressed(&keys, Action::Serve(*player));
 		}
 	}
 
-	pub fn update_position(mut query: Query<(&Paddle, &mut Transform)>, time: Res<Time>) {
-		//  debug!("Updating paddle positions...");
-		let delta_seconds = time.delta_seconds();
+	/// Moves the paddles to where the [`Simulation`] put them, resizing them when the config or a power-up changed.
+	pub fn sync(simulation: Res<Simulation>, mut query: Query<(&mut Paddle, &mut Transform, &mut Sprite, &Player)>) {
+		let config = &simulation.settings.config;
 
-		for (paddle, mut transform) in query.iter_mut() {
-			// debug!(
-			//	 "Setting paddle ({:?}) y position to {},{}",
-			//	 paddle, transform.translation.x, transform.translation.y
-			// );
+		for (mut paddle, mut transform, mut sprite, player) in query.iter_mut() {
+			let state = &simulation.state.paddles[player.index()];
+			let size = Paddle::size(state.height(config), config);
 
-			transform.translation.y = (transform.translation.y + delta_seconds * paddle.velocity.y)
-				.max(-WINDOW_HEIGHT * 0.5 + WINDOW_HEIGHT * 0.1 + Wall::WIDTH)
-				.min(WINDOW_HEIGHT * 0.5 - WINDOW_HEIGHT * 0.1 - Wall::WIDTH);
+			paddle.speed = state.speed;
+			paddle.velocity = Vec2::new(0., state.velocity);
+			transform.translation = Vec3::new(Paddle::x(*player, config), state.y, 0.);
+			if sprite.custom_size != Some(size) {
+				sprite.custom_size = Some(size);
+			}
 		}
 	}
 }
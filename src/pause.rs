@@ -1,6 +1,10 @@
 use bevy::prelude::*;
 
-use crate::GameState;
+use crate::{
+	controls::{Action, KeyBindings},
+	gamepad::GamepadPlugin,
+	GameState,
+};
 
 #[derive(Component)]
 pub struct Pause;
@@ -57,8 +61,12 @@ impl PausePlugin {
 		state: Res<State<GameState>>,
 		mut next_state: ResMut<NextState<GameState>>,
 		keyboard_input: Res<Input<KeyCode>>,
+		gamepad_input: Res<Input<GamepadButton>>,
+		bindings: Res<KeyBindings>,
 	) {
-		if keyboard_input.just_pressed(KeyCode::P) {
+		if bindings.just_pressed(&keyboard_input, Action::Pause)
+			|| GamepadPlugin::just_pressed(&gamepad_input, GamepadButtonType::Start)
+		{
 			match state.0 {
 				GameState::Playing => {
 					next_state.set(GameState::Paused);
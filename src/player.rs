@@ -1,16 +1,41 @@
+use std::str::FromStr;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Component, Debug, PartialEq)]
+#[derive(Clone, Copy, Component, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum Player {
 	Left,
 	Right,
 }
 
 impl Player {
-	pub fn movement_keys(&self) -> (KeyCode, KeyCode) {
+	pub const ALL: [Player; 2] = [Player::Left, Player::Right];
+
+	/// Position of this player in per-player arrays like [`crate::simulation::SimState::paddles`].
+	pub fn index(&self) -> usize {
+		match self {
+			Player::Left => 0,
+			Player::Right => 1,
+		}
+	}
+
+	pub fn opponent(&self) -> Player {
 		match self {
-			Player::Left => (KeyCode::W, KeyCode::S),
-			Player::Right => (KeyCode::Up, KeyCode::Down),
+			Player::Left => Player::Right,
+			Player::Right => Player::Left,
+		}
+	}
+}
+
+impl FromStr for Player {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name.to_lowercase().as_str() {
+			"left" => Ok(Player::Left),
+			"right" => Ok(Player::Right),
+			_ => Err(format!("unknown side `{}`, expected left or right", name)),
 		}
 	}
 }
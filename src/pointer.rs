@@ -0,0 +1,181 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+	controller::{Controller, Controllers},
+	net::NetMode,
+	paddle::{PaddleInput, PaddlePlugin},
+	player::Player,
+	simulation::Simulation,
+	GameSet, GameState,
+};
+
+/// Where the mouse or the touches want each paddle. The left half of the screen steers the left paddle, the
+/// right half the right one, so two players can share a tablet.
+#[derive(Debug, Default, Resource)]
+pub struct Pointers {
+	/// The y coordinate to move towards, by [`Player::index`], `None` while nothing holds that half.
+	pub targets: [Option<f32>; 2],
+	/// Whether a pointer was let go on that half since the last step, which serves.
+	released: [bool; 2],
+}
+
+/// Lets local players drag their paddle with the mouse or a finger. The paddle follows at most at
+/// [`crate::paddle::Paddle::speed`], so it's no faster than with the keyboard.
+pub struct PointerPlugin;
+impl Plugin for PointerPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Pointers>()
+			.add_system(Self::track)
+			.add_system(Self::take_over)
+			.add_system(
+				Self::handle_input
+					.in_set(GameSet::Input)
+					.after(PaddlePlugin::handle_input)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			);
+	}
+}
+
+impl PointerPlugin {
+	fn track(
+		mut pointers: ResMut<Pointers>,
+		window_query: Query<&Window, With<PrimaryWindow>>,
+		camera_query: Query<(&Camera, &GlobalTransform)>,
+		mouse_input: Res<Input<MouseButton>>,
+		touches: Res<Touches>,
+	) {
+		let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single())
+		else {
+			return;
+		};
+
+		// The side and the y coordinate on the field of a position in the window
+		let locate = |position: Vec2| {
+			let player = Self::side(window, position);
+			let ray = camera.viewport_to_world(camera_transform, position)?;
+			Some((player, ray.origin.y))
+		};
+
+		pointers.targets = [None; 2];
+
+		if let Some((player, y)) = window.cursor_position().and_then(locate) {
+			if mouse_input.pressed(MouseButton::Left) {
+				pointers.targets[player.index()] = Some(y);
+			}
+			if mouse_input.just_released(MouseButton::Left) {
+				pointers.released[player.index()] = true;
+			}
+		}
+
+		for touch in touches.iter() {
+			if let Some((player, y)) = locate(Self::touch_position(window, touch.position())) {
+				pointers.targets[player.index()] = Some(y);
+			}
+		}
+		for touch in touches.iter_just_released() {
+			if let Some((player, _)) = locate(Self::touch_position(window, touch.position())) {
+				pointers.released[player.index()] = true;
+			}
+		}
+	}
+
+	/// Touching the bot's half of the screen during a local match hands its paddle to a second player.
+	fn take_over(
+		mut controllers: ResMut<Controllers>,
+		mode: Res<NetMode>,
+		state: Res<State<GameState>>,
+		window_query: Query<&Window, With<PrimaryWindow>>,
+		touches: Res<Touches>,
+	) {
+		if *mode != NetMode::Offline || state.0 == GameState::Menu {
+			return;
+		}
+		let Ok(window) = window_query.get_single() else {
+			return;
+		};
+
+		for touch in touches.iter_just_pressed() {
+			let player = Self::side(window, touch.position());
+			if matches!(controllers.get(player), Controller::Ai(_)) {
+				info!("A second player takes over {:?}", player);
+				controllers.set(player, Controller::Local);
+			}
+		}
+	}
+
+	/// Touches are reported from the top left corner of the window, the cursor and the camera go from the bottom left.
+	fn touch_position(window: &Window, position: Vec2) -> Vec2 {
+		Vec2::new(position.x, window.height() - position.y)
+	}
+
+	/// The player whose half of the window `position` is on.
+	fn side(window: &Window, position: Vec2) -> Player {
+		if position.x < window.width() / 2. {
+			Player::Left
+		} else {
+			Player::Right
+		}
+	}
+
+	/// Moves each held paddle towards its pointer, slowing down so it stops right there.
+	pub fn handle_input(
+		mut pointers: ResMut<Pointers>,
+		simulation: Res<Simulation>,
+		mut query: Query<(&mut PaddleInput, &Player, &Controller)>,
+	) {
+		let released = std::mem::take(&mut pointers.released);
+
+		for (mut input, player, controller) in query.iter_mut() {
+			if *controller != Controller::Local {
+				continue;
+			}
+
+			let index = player.index();
+			if let Some(target) = pointers.targets[index] {
+				let paddle = &simulation.state.paddles[index];
+				let step = paddle.speed * simulation.settings.config.time_step;
+				if step > 0. {
+					input.axis = ((target - paddle.y) / step).clamp(-1., 1.);
+				}
+			}
+			input.serve |= released[index];
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use bevy::{
+		render::camera::{CameraProjection, ScalingMode},
+		window::WindowResolution,
+	};
+
+	use super::*;
+	use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+	#[test]
+	fn touches_near_the_top_point_up_the_field() {
+		let window = Window {
+			resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+			..default()
+		};
+		let position = PointerPlugin::touch_position(&window, Vec2::new(100., 10.));
+		assert_eq!(position, Vec2::new(100., WINDOW_HEIGHT - 10.));
+
+		// What `Camera::viewport_to_world` does with the game's camera, which doesn't move
+		let mut projection = OrthographicProjection {
+			scaling_mode: ScalingMode::AutoMin {
+				min_width: WINDOW_WIDTH,
+				min_height: WINDOW_HEIGHT,
+			},
+			..default()
+		};
+		projection.update(window.width(), window.height());
+		let ndc = position / Vec2::new(window.width(), window.height()) * 2. - Vec2::ONE;
+		let world = projection
+			.get_projection_matrix()
+			.inverse()
+			.project_point3(ndc.extend(1.));
+		assert!(world.y > WINDOW_HEIGHT / 2. - 20., "{:?}", world);
+	}
+}
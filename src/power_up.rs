@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	ball::BallState,
+	collider::Aabb,
+	config::GameConfig,
+	player::Player,
+	simulation::{PaddleState, SimRng, Simulation},
+	GameSet, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+/// What a power-up does, to the player whose paddle touched the ball last before it reached the power-up.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PowerUpKind {
+	/// Makes the player's paddle taller.
+	Grow,
+	/// Makes the opponent's paddle shorter.
+	Shrink,
+	/// Makes the player's paddle faster.
+	Quick,
+}
+
+impl PowerUpKind {
+	pub const ALL: [PowerUpKind; 3] = [PowerUpKind::Grow, PowerUpKind::Shrink, PowerUpKind::Quick];
+
+	/// The player whose paddle changes when `collector` collects the power-up.
+	pub fn target(&self, collector: Player) -> Player {
+		match self {
+			PowerUpKind::Grow | PowerUpKind::Quick => collector,
+			PowerUpKind::Shrink => collector.opponent(),
+		}
+	}
+
+	fn color(&self) -> Color {
+		match self {
+			PowerUpKind::Grow => Color::rgb(0.3, 0.9, 0.4),
+			PowerUpKind::Shrink => Color::rgb(0.9, 0.3, 0.3),
+			PowerUpKind::Quick => Color::rgb(0.3, 0.6, 1.),
+		}
+	}
+}
+
+/// How often power-ups appear and how strong they are, see [`PowerUpKind`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PowerUpPhysics {
+	/// Seconds of play before a power-up appears, counted again after each one is collected and after every goal.
+	pub interval: f32,
+	/// Seconds a power-up lasts, a goal ends it early.
+	pub duration: f32,
+	/// Factor [`PowerUpKind::Grow`] multiplies a paddle's height by, [`PowerUpKind::Shrink`] divides it by.
+	pub grow: f32,
+	/// Factor [`PowerUpKind::Quick`] multiplies a paddle's speed by.
+	pub quick: f32,
+}
+
+impl Default for PowerUpPhysics {
+	fn default() -> Self {
+		Self {
+			interval: 5.,
+			duration: 8.,
+			grow: 1.5,
+			quick: 1.5,
+		}
+	}
+}
+
+/// A power-up waiting on the field for the ball.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PowerUp {
+	pub kind: PowerUpKind,
+	pub position: Vec2,
+}
+
+impl PowerUp {
+	/// Width and height of a power-up in pixels.
+	pub const SIZE: f32 = 30.;
+
+	/// A power-up of a random kind somewhere between the paddles, away from the walls.
+	fn random(rng: &mut SimRng, config: &GameConfig) -> Self {
+		let kind = PowerUpKind::ALL[(rng.u64() % PowerUpKind::ALL.len() as u64) as usize];
+		let max_x = WINDOW_WIDTH / 4.;
+		let max_y = WINDOW_HEIGHT / 2. - config.wall_width - Self::SIZE;
+		Self {
+			kind,
+			position: Vec2::new(rng.f32_normalized() * max_x, rng.f32_normalized() * max_y),
+		}
+	}
+
+	pub fn aabb(&self) -> Aabb {
+		Aabb::new(self.position, Vec2::splat(Self::SIZE))
+	}
+}
+
+/// A power-up working on a paddle.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PowerUpEffect {
+	pub kind: PowerUpKind,
+	pub ticks_left: u32,
+}
+
+/// The power-ups of a match as seen by the [`Simulation`], empty unless [`PowerUpPhysics`] are played with.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PowerUps {
+	/// The power-up on the field, if one appeared.
+	pub field: Option<PowerUp>,
+	/// Steps of play until the next power-up appears.
+	pub ticks_until_next: u32,
+	/// Indexed by [`Player::index`], a new power-up replaces the one a paddle had.
+	pub effects: [Option<PowerUpEffect>; 2],
+	/// The player whose paddle served or hit the ball last, who collects the power-up the ball touches.
+	pub last_hit: Option<Player>,
+}
+
+impl PowerUps {
+	/// No power-ups yet, the first one appears after [`PowerUpPhysics::interval`].
+	pub fn new(physics: &PowerUpPhysics, config: &GameConfig) -> Self {
+		Self {
+			ticks_until_next: Self::ticks(physics.interval, config),
+			..default()
+		}
+	}
+
+	fn ticks(seconds: f32, config: &GameConfig) -> u32 {
+		(seconds / config.time_step).round().max(1.) as u32
+	}
+
+	/// Advances the power-ups by a step of play. Effects run out, the ball collects the power-up it touches and
+	/// a new one appears when it's time.
+	pub fn step(
+		&mut self,
+		physics: &PowerUpPhysics,
+		config: &GameConfig,
+		ball: &BallState,
+		paddles: &mut [PaddleState; 2],
+		rng: &mut SimRng,
+	) {
+		for effect in &mut self.effects {
+			*effect = effect
+				.filter(|effect| effect.ticks_left > 1)
+				.map(|effect| PowerUpEffect {
+					ticks_left: effect.ticks_left - 1,
+					..effect
+				});
+		}
+
+		match (self.field, self.last_hit) {
+			(Some(power_up), Some(collector)) if ball.aabb(config.wall_width).overlaps(&power_up.aabb()) => {
+				debug!("{:?} collected {:?}", collector, power_up.kind);
+				self.field = None;
+				self.ticks_until_next = Self::ticks(physics.interval, config);
+				self.effects[power_up.kind.target(collector).index()] = Some(PowerUpEffect {
+					kind: power_up.kind,
+					ticks_left: Self::ticks(physics.duration, config),
+				});
+			}
+			(Some(_), _) => {}
+			(None, _) if self.ticks_until_next > 1 => self.ticks_until_next -= 1,
+			(None, _) => self.field = Some(PowerUp::random(rng, config)),
+		}
+
+		self.apply(physics, config, paddles);
+	}
+
+	/// Sizes the paddles and sets their speed according to the effects on them.
+	pub fn apply(&self, physics: &PowerUpPhysics, config: &GameConfig, paddles: &mut [PaddleState; 2]) {
+		for (paddle, effect) in paddles.iter_mut().zip(self.effects) {
+			let kind = effect.map(|effect| effect.kind);
+			paddle.scale = match kind {
+				Some(PowerUpKind::Grow) => physics.grow,
+				Some(PowerUpKind::Shrink) => 1. / physics.grow,
+				_ => 1.,
+			};
+			paddle.speed = match kind {
+				Some(PowerUpKind::Quick) => config.paddle_speed * physics.quick,
+				_ => config.paddle_speed,
+			};
+		}
+	}
+}
+
+#[derive(Default, Component)]
+pub struct PowerUpSprite;
+
+/// Shows the power-up on the field, paddles show their effects by their size.
+pub struct PowerUpPlugin;
+impl Plugin for PowerUpPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_startup_system(Self::setup)
+			.add_system(Self::sync.in_set(GameSet::Sync).in_schedule(CoreSchedule::FixedUpdate));
+	}
+}
+
+impl PowerUpPlugin {
+	fn setup(mut commands: Commands) {
+		commands.spawn((
+			Name::new("Power-Up"),
+			PowerUpSprite,
+			SpriteBundle {
+				sprite: Sprite {
+					custom_size: Some(Vec2::splat(PowerUp::SIZE)),
+					..default()
+				},
+				visibility: Visibility::Hidden,
+				..default()
+			},
+		));
+	}
+
+	fn sync(
+		simulation: Res<Simulation>,
+		mut query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<PowerUpSprite>>,
+	) {
+		let field = simulation.state.power_ups.field;
+
+		for (mut transform, mut sprite, mut visibility) in query.iter_mut() {
+			let Some(power_up) = field else {
+				*visibility = Visibility::Hidden;
+				continue;
+			};
+
+			*visibility = Visibility::Visible;
+			transform.translation = power_up.position.extend(0.);
+			sprite.color = power_up.kind.color();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A power-up of `kind` in the middle of the field, where the ball starts out.
+	fn waiting(kind: PowerUpKind, last_hit: Option<Player>) -> PowerUps {
+		PowerUps {
+			field: Some(PowerUp {
+				kind,
+				position: Vec2::ZERO,
+			}),
+			last_hit,
+			..default()
+		}
+	}
+
+	fn new_paddles(config: &GameConfig) -> [PaddleState; 2] {
+		[PaddleState::new(config.paddle_speed); 2]
+	}
+
+	#[test]
+	fn the_player_who_hit_the_ball_collects_the_power_up() {
+		let (physics, config) = (PowerUpPhysics::default(), GameConfig::default());
+		let mut paddles = new_paddles(&config);
+		let mut power_ups = waiting(PowerUpKind::Grow, Some(Player::Right));
+
+		let ball = BallState::new(Vec2::X, config.ball_speed);
+		power_ups.step(&physics, &config, &ball, &mut paddles, &mut SimRng::new(0));
+
+		assert_eq!(power_ups.field, None);
+		assert_eq!(paddles[Player::Left.index()].scale, 1.);
+		assert_eq!(paddles[Player::Right.index()].scale, physics.grow);
+	}
+
+	#[test]
+	fn shrinking_and_quick_power_ups_change_the_right_paddle() {
+		let (physics, config) = (PowerUpPhysics::default(), GameConfig::default());
+		let ball = BallState::new(Vec2::X, config.ball_speed);
+
+		let mut paddles = new_paddles(&config);
+		let mut power_ups = waiting(PowerUpKind::Shrink, Some(Player::Left));
+		power_ups.step(&physics, &config, &ball, &mut paddles, &mut SimRng::new(0));
+		assert_eq!(paddles[Player::Right.index()].scale, 1. / physics.grow);
+		assert_eq!(paddles[Player::Left.index()].scale, 1.);
+
+		let mut paddles = new_paddles(&config);
+		let mut power_ups = waiting(PowerUpKind::Quick, Some(Player::Left));
+		power_ups.step(&physics, &config, &ball, &mut paddles, &mut SimRng::new(0));
+		assert_eq!(paddles[Player::Left.index()].speed, config.paddle_speed * physics.quick);
+		assert_eq!(paddles[Player::Right.index()].speed, config.paddle_speed);
+	}
+
+	#[test]
+	fn unhit_balls_pass_over_power_ups() {
+		let (physics, config) = (PowerUpPhysics::default(), GameConfig::default());
+		let mut power_ups = waiting(PowerUpKind::Grow, None);
+
+		let ball = BallState::new(Vec2::X, config.ball_speed);
+		power_ups.step(&physics, &config, &ball, &mut new_paddles(&config), &mut SimRng::new(0));
+
+		assert!(power_ups.field.is_some());
+	}
+
+	#[test]
+	fn power_ups_run_out_and_come_back() {
+		let (physics, config) = (PowerUpPhysics::default(), GameConfig::default());
+		let mut paddles = new_paddles(&config);
+		let mut power_ups = waiting(PowerUpKind::Grow, Some(Player::Left));
+		let mut rng = SimRng::new(0);
+
+		let ball = BallState::new(Vec2::X, config.ball_speed);
+		power_ups.step(&physics, &config, &ball, &mut paddles, &mut rng);
+		assert_eq!(paddles[Player::Left.index()].scale, physics.grow);
+
+		// Out of the way of the next power-up
+		let away = BallState {
+			position: Vec2::new(0., WINDOW_HEIGHT),
+			..ball
+		};
+		let interval = PowerUps::ticks(physics.interval, &config);
+		let duration = PowerUps::ticks(physics.duration, &config);
+		for _ in 0..duration {
+			power_ups.step(&physics, &config, &away, &mut paddles, &mut rng);
+		}
+		assert_eq!(paddles[Player::Left.index()].scale, 1.);
+		assert!(interval < duration);
+		assert!(power_ups.field.is_some());
+	}
+}
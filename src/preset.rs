@@ -0,0 +1,328 @@
+//! Named ways to play, see [`Preset`].
+
+use std::{
+	fmt, fs, io,
+	path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	ball::BallPhysics,
+	config::{ConfigError, GameConfig, TunedConfig},
+	files::{self, FileError},
+	rules::MatchRules,
+};
+
+/// The presets shipped with the game, the first one is played unless another one is picked.
+const SHIPPED: [&str; 4] = [
+	include_str!("../assets/presets/classic.preset.json"),
+	include_str!("../assets/presets/arcade.preset.json"),
+	include_str!("../assets/presets/tournament.preset.json"),
+	include_str!("../assets/presets/lunchtime.preset.json"),
+];
+
+/// The rules, the ball's physics and possibly the tuning of a way to play. Left-out fields keep their defaults.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Preset {
+	pub name: String,
+	#[serde(default)]
+	pub rules: MatchRules,
+	#[serde(default)]
+	pub physics: BallPhysics,
+	/// Replaces the [`TunedConfig`], `None` plays with it.
+	#[serde(default)]
+	pub config: Option<GameConfig>,
+}
+
+/// Why a [`Preset`] couldn't be loaded, saved or found.
+#[derive(Debug)]
+pub enum PresetError {
+	File(FileError),
+	Invalid(ConfigError),
+	/// No preset has this name, with the names of those that exist.
+	Unknown {
+		name: String,
+		known: Vec<String>,
+	},
+}
+
+impl fmt::Display for PresetError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PresetError::File(error) => error.describe(formatter, "the preset"),
+			PresetError::Invalid(error) => write!(formatter, "the preset can't be played, {}", error),
+			PresetError::Unknown { name, known } => write!(
+				formatter,
+				"there's no preset called `{}`, try one of {} or the path of a `.{}` file",
+				name,
+				known.join(", "),
+				Preset::EXTENSION
+			),
+		}
+	}
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<FileError> for PresetError {
+	fn from(error: FileError) -> Self {
+		PresetError::File(error)
+	}
+}
+
+impl From<ConfigError> for PresetError {
+	fn from(error: ConfigError) -> Self {
+		PresetError::Invalid(error)
+	}
+}
+
+impl Preset {
+	pub const EXTENSION: &'static str = "preset.json";
+
+	/// Reads a preset and checks that the game can be played with it.
+	pub fn from_json(json: &[u8]) -> Result<Self, PresetError> {
+		let preset: Self = serde_json::from_slice(json).map_err(FileError::from)?;
+		preset.validate()?;
+		Ok(preset)
+	}
+
+	pub fn validate(&self) -> Result<(), ConfigError> {
+		let rules = &self.rules;
+		GameConfig::check(
+			"target_score",
+			rules.target_score as f32,
+			rules.target_score >= 1,
+			"at least 1",
+		)?;
+		if let Some(time_limit) = rules.time_limit {
+			GameConfig::check(
+				"time_limit",
+				time_limit.as_secs_f32(),
+				!time_limit.is_zero(),
+				"more than 0",
+			)?;
+		}
+		let physics = &self.physics;
+		GameConfig::check(
+			"speed_up",
+			physics.speed_up,
+			physics.speed_up >= 1.,
+			"at least 1, the ball never slows down",
+		)?;
+		GameConfig::check("max_speed", physics.max_speed, physics.max_speed > 0., "more than 0")?;
+		if let Some(power_ups) = &physics.power_ups {
+			GameConfig::check("interval", power_ups.interval, power_ups.interval > 0., "more than 0")?;
+			GameConfig::check("duration", power_ups.duration, power_ups.duration > 0., "more than 0")?;
+			GameConfig::check(
+				"grow",
+				power_ups.grow,
+				power_ups.grow >= 1.,
+				"at least 1, Shrink divides by it",
+			)?;
+			GameConfig::check("quick", power_ups.quick, power_ups.quick > 0., "more than 0")?;
+		}
+		match &self.config {
+			Some(config) => config.validate(),
+			None => Ok(()),
+		}
+	}
+
+	/// The config to play this preset with, when the config file holds `tuned`.
+	pub fn config(&self, tuned: &GameConfig) -> GameConfig {
+		self.config.clone().unwrap_or_else(|| tuned.clone())
+	}
+
+	/// The name of the file the preset is saved to, e.g. `my-preset.preset.json` for "My Preset".
+	pub fn file_name(&self) -> String {
+		format!("{}.{}", self.name.to_lowercase().replace(' ', "-"), Self::EXTENSION)
+	}
+
+	/// Saves the preset into `directory`, returns the path of the file.
+	pub fn save(&self, directory: &Path) -> Result<PathBuf, PresetError> {
+		let path = directory.join(self.file_name());
+		files::write_json(&path, self)?;
+		Ok(path)
+	}
+
+	pub fn load(path: &Path) -> Result<Self, PresetError> {
+		Self::from_json(files::read(path)?.as_bytes())
+	}
+
+	/// The shipped or saved preset called `name`, see [`Presets::select_named`].
+	pub fn find(name: &str) -> Result<Self, PresetError> {
+		let mut presets = Presets::load(PresetConfig::default().directory.as_deref());
+		presets.select_named(name).cloned()
+	}
+}
+
+/// Every [`Preset`] to choose from and the one being played.
+///
+/// Picking another one changes the rules and physics of the next match, and the config right away.
+#[derive(Clone, Debug, Resource)]
+pub struct Presets {
+	all: Vec<Preset>,
+	selected: usize,
+}
+
+impl Default for Presets {
+	/// The shipped presets, with the first one selected.
+	fn default() -> Self {
+		let all = SHIPPED
+			.iter()
+			.map(|json| Preset::from_json(json.as_bytes()).expect("the shipped presets are valid"))
+			.collect();
+		Self { all, selected: 0 }
+	}
+}
+
+impl Presets {
+	/// The shipped presets and those saved in `directory`, skipping files that can't be played.
+	pub fn load(directory: Option<&Path>) -> Self {
+		let mut presets = Self::default();
+		let Some(directory) = directory else {
+			return presets;
+		};
+		let entries = match fs::read_dir(directory) {
+			Ok(entries) => entries,
+			// Nothing was ever saved
+			Err(error) if error.kind() == io::ErrorKind::NotFound => return presets,
+			Err(error) => {
+				warn!("Failed to read the presets in {}: {}", directory.display(), error);
+				return presets;
+			}
+		};
+
+		let mut paths: Vec<_> = entries
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.filter(|path| path.to_string_lossy().ends_with(Preset::EXTENSION))
+			.collect();
+		paths.sort();
+		for path in paths {
+			match Preset::load(&path) {
+				Ok(preset) => {
+					presets.add(preset);
+				}
+				Err(error) => warn!("Skipping the preset {}, {}", path.display(), error),
+			}
+		}
+		presets
+	}
+
+	pub fn all(&self) -> &[Preset] {
+		&self.all
+	}
+
+	pub fn selected(&self) -> &Preset {
+		&self.all[self.selected]
+	}
+
+	pub fn select(&mut self, index: usize) {
+		self.selected = index.min(self.all.len() - 1);
+	}
+
+	pub fn select_next(&mut self) {
+		self.selected = (self.selected + 1) % self.all.len();
+	}
+
+	/// Adds `preset`, replacing the one with the same name. Returns its index.
+	pub fn add(&mut self, preset: Preset) -> usize {
+		match self.position(&preset.name) {
+			Some(index) => {
+				self.all[index] = preset;
+				index
+			}
+			None => {
+				self.all.push(preset);
+				self.all.len() - 1
+			}
+		}
+	}
+
+	fn position(&self, name: &str) -> Option<usize> {
+		self.all
+			.iter()
+			.position(|preset| preset.name.eq_ignore_ascii_case(name))
+	}
+
+	/// Selects the preset called `name`, or the one saved at `name` if it ends with [`Preset::EXTENSION`].
+	pub fn select_named(&mut self, name: &str) -> Result<&Preset, PresetError> {
+		let index = if name.ends_with(Preset::EXTENSION) {
+			let preset = Preset::load(Path::new(name))?;
+			self.add(preset)
+		} else {
+			self.position(name).ok_or_else(|| PresetError::Unknown {
+				name: name.to_owned(),
+				known: self.all.iter().map(|preset| preset.name.to_lowercase()).collect(),
+			})?
+		};
+		self.selected = index;
+		Ok(self.selected())
+	}
+
+	/// Plays the selected preset from the first match on, to be called before the app runs.
+	pub fn insert_into(self, app: &mut App) {
+		let preset = self.selected();
+		app.insert_resource(preset.rules.clone())
+			.insert_resource(preset.physics.clone());
+		if let Some(config) = &preset.config {
+			app.insert_resource(config.clone());
+		}
+		app.insert_resource(self);
+	}
+}
+
+/// Where custom presets are saved and loaded from.
+#[derive(Clone, Debug, Resource)]
+pub struct PresetConfig {
+	/// `None` forgets saved presets when the game closes.
+	pub directory: Option<PathBuf>,
+}
+
+impl Default for PresetConfig {
+	fn default() -> Self {
+		Self {
+			directory: files::data_path("presets"),
+		}
+	}
+}
+
+/// Plays the selected [`Preset`], on top of the [`TunedConfig`] unless it brings its own config.
+pub struct PresetPlugin;
+impl Plugin for PresetPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Presets>()
+			.init_resource::<PresetConfig>()
+			.add_system(Self::apply);
+	}
+}
+
+impl PresetPlugin {
+	/// Resources inserted before the app ran were already set up with the selected preset.
+	pub fn apply(
+		presets: Res<Presets>,
+		tuned: Res<TunedConfig>,
+		mut rules: ResMut<MatchRules>,
+		mut physics: ResMut<BallPhysics>,
+		mut config: ResMut<GameConfig>,
+	) {
+		let picked = presets.is_changed() && !presets.is_added();
+		let retuned = tuned.is_changed() && !tuned.is_added();
+		if !picked && !retuned {
+			return;
+		}
+
+		let preset = presets.selected();
+		if picked {
+			info!("Playing the {} preset", preset.name);
+			*rules = preset.rules.clone();
+			*physics = preset.physics.clone();
+		}
+
+		let preset_config = preset.config(&tuned.0);
+		if *config != preset_config {
+			*config = preset_config;
+		}
+	}
+}
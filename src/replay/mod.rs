@@ -0,0 +1,263 @@
+use std::{fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	files::{self, FileError},
+	paddle::PaddleInput,
+	player::Player,
+	score::Score,
+	simulation::{Phase, SimSettings, SimState},
+};
+
+mod recorder;
+mod viewer;
+
+pub use recorder::{Recorder, ReplayConfig, ReplayPlugin};
+pub use viewer::{OpenReplay, ReplayViewer, ReplayViewerPlugin};
+
+/// Marks a file as a replay of this game.
+pub const REPLAY_FORMAT: &str = "bevy-pong-replay";
+/// Bumped whenever a change to the replay format or to the [`crate::simulation`] makes old replays play differently.
+pub const REPLAY_VERSION: u32 = 2;
+
+/// Tells what wrote a replay, before anything else of it is read.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReplayHeader {
+	pub format: String,
+	pub version: u32,
+}
+
+impl Default for ReplayHeader {
+	fn default() -> Self {
+		Self {
+			format: REPLAY_FORMAT.to_string(),
+			version: REPLAY_VERSION,
+		}
+	}
+}
+
+/// The same inputs for a number of steps in a row.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct InputRun {
+	pub steps: u32,
+	/// Indexed by [`Player::index`].
+	pub inputs: [PaddleInput; 2],
+}
+
+/// Everything needed to play a match again, step by step: the [`SimState::seed`],
+/// the [`SimSettings`] and the inputs of both players.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Replay {
+	pub header: ReplayHeader,
+	pub seed: u64,
+	pub settings: SimSettings,
+	/// Inputs of every step, run-length encoded.
+	pub inputs: Vec<InputRun>,
+	/// How the match ended, `None` if it was cut short.
+	pub winner: Option<Player>,
+	pub score: Score,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+	File(FileError),
+	/// The file isn't a replay at all.
+	NotAReplay,
+	/// The replay was written by a version of the game that plays differently.
+	UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ReplayError::File(error) => error.describe(formatter, "the replay"),
+			ReplayError::NotAReplay => write!(formatter, "not a replay"),
+			ReplayError::UnsupportedVersion(version) => write!(
+				formatter,
+				"replay version {} can't be played, this game plays version {}",
+				version, REPLAY_VERSION
+			),
+		}
+	}
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<FileError> for ReplayError {
+	fn from(error: FileError) -> Self {
+		ReplayError::File(error)
+	}
+}
+
+impl Replay {
+	pub fn new(seed: u64, settings: SimSettings) -> Self {
+		Self {
+			header: ReplayHeader::default(),
+			seed,
+			settings,
+			inputs: Vec::new(),
+			winner: None,
+			score: Score::default(),
+		}
+	}
+
+	/// Appends the inputs of the next step.
+	pub fn push(&mut self, inputs: [PaddleInput; 2]) {
+		match self.inputs.last_mut() {
+			Some(run) if run.inputs == inputs => run.steps += 1,
+			_ => self.inputs.push(InputRun { steps: 1, inputs }),
+		}
+	}
+
+	/// Number of steps recorded.
+	pub fn ticks(&self) -> u64 {
+		self.inputs.iter().map(|run| u64::from(run.steps)).sum()
+	}
+
+	/// The inputs of every step, in order.
+	pub fn steps(&self) -> impl Iterator<Item = [PaddleInput; 2]> + '_ {
+		self.inputs
+			.iter()
+			.flat_map(|run| std::iter::repeat(run.inputs).take(run.steps as usize))
+	}
+
+	/// The state the recorded match started in.
+	pub fn initial_state(&self) -> SimState {
+		SimState::new(&self.settings, self.seed, false)
+	}
+
+	/// Records how the match in `state` ended.
+	pub fn finish(&mut self, state: &SimState) {
+		self.score = state.score;
+		self.winner = match state.phase {
+			Phase::GameOver { winner } => Some(winner),
+			_ => None,
+		};
+	}
+
+	pub fn to_json(&self) -> Result<String, ReplayError> {
+		Ok(serde_json::to_string(self).map_err(FileError::from)?)
+	}
+
+	/// Reads a replay, refusing ones with an unknown [`ReplayHeader`].
+	pub fn from_json(json: &str) -> Result<Self, ReplayError> {
+		#[derive(Deserialize)]
+		struct Versioned {
+			header: ReplayHeader,
+		}
+
+		let value: serde_json::Value = serde_json::from_str(json).map_err(FileError::from)?;
+		let Ok(Versioned { header }) = serde_json::from_value(value.clone()) else {
+			return Err(ReplayError::NotAReplay);
+		};
+		if header.format != REPLAY_FORMAT {
+			return Err(ReplayError::NotAReplay);
+		}
+
+		match header.version {
+			REPLAY_VERSION => Ok(serde_json::from_value(value).map_err(FileError::from)?),
+			// Power-ups came with version 2, version 1 replays play the same without them
+			1 => {
+				let replay: Self = serde_json::from_value(value).map_err(FileError::from)?;
+				Ok(Self {
+					header: ReplayHeader::default(),
+					..replay
+				})
+			}
+			version => Err(ReplayError::UnsupportedVersion(version)),
+		}
+	}
+
+	pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+		Ok(files::write(path, &self.to_json()?)?)
+	}
+
+	pub fn load(path: &Path) -> Result<Self, ReplayError> {
+		Self::from_json(&files::read(path)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn input(axis: f32, serve: bool) -> PaddleInput {
+		PaddleInput { axis, serve }
+	}
+
+	fn replay() -> Replay {
+		let mut replay = Replay::new(7, SimSettings::default());
+		let steps = [
+			[input(0., true), input(0., false)],
+			[input(0., true), input(0., false)],
+			[input(1., false), input(-0.5, false)],
+			[input(0., true), input(0., false)],
+		];
+		for inputs in steps {
+			replay.push(inputs);
+		}
+		replay
+	}
+
+	/// `replay` as JSON, with its header changed by `change`.
+	fn with_header(change: impl FnOnce(&mut serde_json::Value)) -> String {
+		let mut value = serde_json::to_value(replay()).unwrap();
+		change(&mut value["header"]);
+		value.to_string()
+	}
+
+	#[test]
+	fn identical_steps_are_stored_once() {
+		let replay = replay();
+
+		assert_eq!(replay.inputs.iter().map(|run| run.steps).collect::<Vec<_>>(), [2, 1, 1]);
+		assert_eq!(replay.ticks(), 4);
+		assert_eq!(
+			replay.steps().collect::<Vec<_>>(),
+			[
+				[input(0., true), input(0., false)],
+				[input(0., true), input(0., false)],
+				[input(1., false), input(-0.5, false)],
+				[input(0., true), input(0., false)],
+			]
+		);
+	}
+
+	#[test]
+	fn replays_read_back_as_written() {
+		let replay = replay();
+		let read = Replay::from_json(&replay.to_json().unwrap()).unwrap();
+		assert_eq!(read, replay);
+	}
+
+	#[test]
+	fn other_files_are_not_replays() {
+		let json = with_header(|header| header["format"] = "bevy-pong-preset".into());
+		assert!(matches!(Replay::from_json(&json), Err(ReplayError::NotAReplay)));
+		assert!(matches!(
+			Replay::from_json(r#"{ "name": "Classic" }"#),
+			Err(ReplayError::NotAReplay)
+		));
+		assert!(matches!(Replay::from_json("pong"), Err(ReplayError::File(_))));
+	}
+
+	#[test]
+	fn replays_from_before_power_ups_still_play() {
+		let json = with_header(|header| header["version"] = 1.into());
+		let read = Replay::from_json(&json).expect("version 1 replays should be migrated");
+
+		assert_eq!(read.header.version, REPLAY_VERSION);
+		assert_eq!(read.settings.physics.power_ups, None);
+		assert_eq!(read.inputs, replay().inputs);
+	}
+
+	#[test]
+	fn replays_of_other_versions_are_refused() {
+		let json = with_header(|header| header["version"] = (REPLAY_VERSION + 1).into());
+		assert!(matches!(
+			Replay::from_json(&json),
+			Err(ReplayError::UnsupportedVersion(version)) if version == REPLAY_VERSION + 1
+		));
+	}
+}
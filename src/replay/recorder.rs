@@ -0,0 +1,107 @@
+use std::{
+	path::PathBuf,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+	files,
+	net::NetPlugin,
+	paddle::PaddleInput,
+	player::Player,
+	simulation::{Phase, Simulation, SimulationPlugin},
+};
+
+use super::Replay;
+
+/// Where finished matches are saved, see [`ReplayPlugin`].
+#[derive(Clone, Debug, Resource)]
+pub struct ReplayConfig {
+	/// `None` keeps replays in memory only.
+	pub directory: Option<PathBuf>,
+}
+
+impl Default for ReplayConfig {
+	fn default() -> Self {
+		Self {
+			directory: files::data_path("replays"),
+		}
+	}
+}
+
+/// The match being recorded, and the last one that ended.
+#[derive(Debug, Default, Resource)]
+pub struct Recorder {
+	pub recording: Option<Replay>,
+	pub last: Option<Replay>,
+	/// Where `last` was saved, if it was.
+	pub last_path: Option<PathBuf>,
+}
+
+/// Records the inputs of every step of a match and saves them as a [`Replay`] once the match is over.
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ReplayConfig>()
+			.init_resource::<Recorder>()
+			.add_system(
+				Self::record
+					.after(SimulationPlugin::step)
+					.run_if(SimulationPlugin::is_simulating)
+					.run_if(NetPlugin::is_authoritative)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			);
+	}
+}
+
+impl ReplayPlugin {
+	/// Runs right after every [`SimulationPlugin::step`], with the inputs that step used.
+	fn record(
+		mut recorder: ResMut<Recorder>,
+		simulation: Res<Simulation>,
+		config: Res<ReplayConfig>,
+		input_query: Query<(&PaddleInput, &Player)>,
+	) {
+		let state = &simulation.state;
+		// Nobody wants to watch the attract mode again
+		if state.attract {
+			recorder.recording = None;
+			return;
+		}
+
+		// A new match started, dropping a recording that was cut short by a reset
+		if state.tick == 1 {
+			recorder.recording = Some(Replay::new(state.seed, simulation.settings.clone()));
+		}
+
+		let Some(replay) = recorder.recording.as_mut() else {
+			return;
+		};
+		replay.push(SimulationPlugin::inputs(&input_query));
+
+		let Phase::GameOver { .. } = state.phase else {
+			return;
+		};
+
+		replay.finish(state);
+		let mut saved = None;
+		if let Some(directory) = &config.directory {
+			let time = SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_or(0, |time| time.as_secs());
+			let path = directory.join(format!("{}-{}.json", time, replay.seed));
+
+			match replay.save(&path) {
+				Ok(()) => {
+					info!("Saved replay to {}", path.display());
+					saved = Some(path);
+				}
+				Err(error) => error!("Failed to save replay to {}: {}", path.display(), error),
+			}
+		}
+		let replay = recorder.recording.take();
+		recorder.last = replay;
+		recorder.last_path = saved;
+	}
+}
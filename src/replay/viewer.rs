@@ -0,0 +1,326 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+	ball::CollisionEvent,
+	paddle::PaddleInput,
+	reset::Reset,
+	simulation::{SimEvent, SimState, Simulation},
+	GameState,
+};
+
+use super::{recorder::Recorder, Replay};
+
+/// Steps between the states that seeking re-simulates from.
+const CHECKPOINT_INTERVAL: u64 = 300;
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 8.;
+/// Seconds skipped by seeking while playing.
+const SEEK_SECONDS: f32 = 5.;
+/// Seconds of play shown before a goal that was seeked to.
+const GOAL_LEAD_SECONDS: f32 = 2.;
+
+/// Asks for a replay to be shown in [`GameState::Replay`].
+pub enum OpenReplay {
+	File(PathBuf),
+	/// The last match played, see [`Recorder::last`].
+	Last,
+}
+
+/// A replay being watched, with everything needed to jump around in it.
+#[derive(Resource)]
+pub struct ReplayViewer {
+	replay: Replay,
+	inputs: Vec<[PaddleInput; 2]>,
+	/// The state every [`CHECKPOINT_INTERVAL`] steps, starting with the initial state.
+	checkpoints: Vec<SimState>,
+	/// Ticks shortly before each goal, see [`GOAL_LEAD_SECONDS`].
+	goals: Vec<u64>,
+	pub paused: bool,
+	pub speed: f32,
+	/// Seconds played that didn't add up to a full step yet.
+	elapsed: f32,
+}
+
+impl ReplayViewer {
+	/// Plays the whole replay once to find its checkpoints and goals.
+	pub fn new(replay: Replay) -> Self {
+		let inputs: Vec<_> = replay.steps().collect();
+		let mut state = replay.initial_state();
+		let mut checkpoints = vec![state.clone()];
+		let mut goals = Vec::new();
+		let goal_lead = (GOAL_LEAD_SECONDS / replay.settings.config.time_step) as u64;
+
+		for step_inputs in &inputs {
+			let events = state.step(&replay.settings, step_inputs);
+			if events
+				.iter()
+				.any(|event| matches!(event, SimEvent::Collision(CollisionEvent::Goal { .. })))
+			{
+				goals.push(state.tick.saturating_sub(goal_lead));
+			}
+			if state.tick % CHECKPOINT_INTERVAL == 0 {
+				checkpoints.push(state.clone());
+			}
+		}
+
+		if state.score != replay.score {
+			warn!(
+				"Replay ended {} but was recorded as {}, it was made by a different version of the game",
+				state.score, replay.score
+			);
+		}
+
+		Self {
+			replay,
+			inputs,
+			checkpoints,
+			goals,
+			paused: false,
+			speed: 1.,
+			elapsed: 0.,
+		}
+	}
+
+	pub fn ticks(&self) -> u64 {
+		self.inputs.len() as u64
+	}
+
+	/// Steps `state` once, or returns `None` at the end of the replay.
+	fn step(&self, state: &mut SimState) -> Option<Vec<SimEvent>> {
+		let inputs = self.inputs.get(state.tick as usize)?;
+		Some(state.step(&self.replay.settings, inputs))
+	}
+
+	/// The state after `tick` steps, re-simulated from the closest checkpoint before it.
+	pub fn seek(&self, tick: u64) -> SimState {
+		let tick = tick.min(self.ticks());
+		let mut state = self.checkpoints[(tick / CHECKPOINT_INTERVAL) as usize].clone();
+		while state.tick < tick && self.step(&mut state).is_some() {}
+		state
+	}
+
+	/// The first goal coming up after `tick`.
+	fn next_goal(&self, tick: u64) -> Option<u64> {
+		self.goals.iter().copied().find(|goal| *goal > tick)
+	}
+
+	/// The last goal coming up before `tick`, or the start of the replay.
+	fn previous_goal(&self, tick: u64) -> u64 {
+		self.goals.iter().copied().rev().find(|goal| *goal < tick).unwrap_or(0)
+	}
+
+	fn seconds(&self, ticks: u64) -> f32 {
+		ticks as f32 * self.replay.settings.config.time_step
+	}
+}
+
+#[derive(Component)]
+pub struct ReplayScreen;
+
+#[derive(Component)]
+pub struct ReplayStatusText;
+
+pub struct ReplayViewerPlugin;
+impl Plugin for ReplayViewerPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<OpenReplay>()
+			.add_system(Self::open)
+			.add_system(Self::setup.in_schedule(OnEnter(GameState::Replay)))
+			.add_system(Self::cleanup.in_schedule(OnExit(GameState::Replay)))
+			.add_systems(
+				(Self::handle_input, Self::play, Self::update_status)
+					.chain()
+					.in_set(OnUpdate(GameState::Replay)),
+			);
+	}
+}
+
+impl ReplayViewerPlugin {
+	fn open(
+		mut commands: Commands,
+		mut open_reader: EventReader<OpenReplay>,
+		mut next_state: ResMut<NextState<GameState>>,
+		recorder: Res<Recorder>,
+	) {
+		let Some(open) = open_reader.iter().last() else {
+			return;
+		};
+
+		let replay = match open {
+			OpenReplay::File(path) => match Replay::load(path) {
+				Ok(replay) => replay,
+				Err(error) => {
+					error!("Failed to open replay {}: {}", path.display(), error);
+					return;
+				}
+			},
+			OpenReplay::Last => match &recorder.last {
+				Some(replay) => replay.clone(),
+				None => return,
+			},
+		};
+
+		commands.insert_resource(ReplayViewer::new(replay));
+		next_state.set(GameState::Replay);
+	}
+
+	fn setup(
+		mut commands: Commands,
+		mut simulation: ResMut<Simulation>,
+		asset_server: Res<AssetServer>,
+		viewer: Res<ReplayViewer>,
+	) {
+		simulation.settings = viewer.replay.settings.clone();
+		simulation.state = viewer.seek(0);
+
+		let font = asset_server.load("fonts/Pixelzim 3x5.ttf");
+		commands
+			.spawn((
+				Name::new("Replay Screen"),
+				ReplayScreen,
+				NodeBundle {
+					style: Style {
+						align_items: AlignItems::Center,
+						flex_direction: FlexDirection::Column,
+						justify_content: JustifyContent::FlexEnd,
+						position_type: PositionType::Absolute,
+						padding: UiRect::bottom(Val::Px(viewer.replay.settings.config.wall_width * 2.)),
+						size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+						..default()
+					},
+					..default()
+				},
+			))
+			.with_children(|commands| {
+				commands.spawn((
+					Name::new("Replay Status Text"),
+					ReplayStatusText,
+					TextBundle::from_section(
+						"",
+						TextStyle {
+							color: Color::WHITE,
+							font_size: 50.0,
+							font: font.clone(),
+						},
+					),
+				));
+				commands.spawn((
+					Name::new("Replay Controls Text"),
+					TextBundle::from_section(
+						"SPACE: pause   LEFT/RIGHT: seek   PG UP/DN: goals   UP/DOWN: speed   M: menu",
+						TextStyle {
+							color: Color::GRAY,
+							font_size: 25.0,
+							font,
+						},
+					),
+				));
+			});
+	}
+
+	fn cleanup(mut commands: Commands, query: Query<Entity, With<ReplayScreen>>) {
+		for entity in query.iter() {
+			commands.entity(entity).despawn_recursive();
+		}
+		commands.remove_resource::<ReplayViewer>();
+	}
+
+	fn handle_input(
+		mut viewer: ResMut<ReplayViewer>,
+		mut simulation: ResMut<Simulation>,
+		mut next_state: ResMut<NextState<GameState>>,
+		mut reset_writer: EventWriter<Reset>,
+		keyboard_input: Res<Input<KeyCode>>,
+	) {
+		let tick = simulation.state.tick;
+		let seek_steps = (SEEK_SECONDS / viewer.replay.settings.config.time_step) as u64;
+
+		let target = if keyboard_input.just_pressed(KeyCode::Space) {
+			viewer.paused = !viewer.paused;
+			None
+		} else if keyboard_input.just_pressed(KeyCode::Up) {
+			viewer.speed = (viewer.speed * 2.).min(MAX_SPEED);
+			None
+		} else if keyboard_input.just_pressed(KeyCode::Down) {
+			viewer.speed = (viewer.speed / 2.).max(MIN_SPEED);
+			None
+		} else if keyboard_input.just_pressed(KeyCode::Right) {
+			Some(tick + if viewer.paused { 1 } else { seek_steps })
+		} else if keyboard_input.just_pressed(KeyCode::Left) {
+			Some(tick.saturating_sub(if viewer.paused { 1 } else { seek_steps }))
+		} else if keyboard_input.just_pressed(KeyCode::PageUp) {
+			Some(viewer.previous_goal(tick))
+		} else if keyboard_input.just_pressed(KeyCode::PageDown) {
+			viewer.next_goal(tick)
+		} else if keyboard_input.just_pressed(KeyCode::Home) {
+			Some(0)
+		} else if keyboard_input.just_pressed(KeyCode::M) {
+			reset_writer.send(Reset::Hard);
+			next_state.set(GameState::Menu);
+			None
+		} else {
+			None
+		};
+
+		if let Some(target) = target {
+			simulation.state = viewer.seek(target);
+			viewer.elapsed = 0.;
+		}
+	}
+
+	fn play(
+		mut viewer: ResMut<ReplayViewer>,
+		mut simulation: ResMut<Simulation>,
+		mut collision_events: EventWriter<CollisionEvent>,
+		time: Res<Time>,
+	) {
+		if viewer.paused {
+			return;
+		}
+
+		let time_step = viewer.replay.settings.config.time_step;
+		viewer.elapsed += time.delta_seconds() * viewer.speed;
+
+		while viewer.elapsed >= time_step {
+			viewer.elapsed -= time_step;
+
+			let Some(events) = viewer.step(&mut simulation.state) else {
+				// Stop at the end, so the final score stays on screen
+				viewer.paused = true;
+				viewer.elapsed = 0.;
+				break;
+			};
+
+			for event in events {
+				if let SimEvent::Collision(collision) = event {
+					collision_events.send(collision);
+				}
+			}
+		}
+	}
+
+	fn update_status(
+		viewer: Res<ReplayViewer>,
+		simulation: Res<Simulation>,
+		mut query: Query<&mut Text, With<ReplayStatusText>>,
+	) {
+		let clock = |seconds: f32| format!("{}:{:0>2}", seconds as u32 / 60, seconds as u32 % 60);
+		let status = format!(
+			"REPLAY   {}x   {} / {}{}",
+			viewer.speed,
+			clock(viewer.seconds(simulation.state.tick)),
+			clock(viewer.seconds(viewer.ticks())),
+			if viewer.paused { "   PAUSED" } else { "" }
+		);
+
+		for mut text in query.iter_mut() {
+			if let Some(section) = text.sections.get_mut(0) {
+				if section.value != status {
+					section.value = status.clone();
+				}
+			}
+		}
+	}
+}
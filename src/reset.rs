@@ -1,6 +1,10 @@
 use bevy::prelude::*;
 
-use crate::GameState;
+use crate::{
+	controls::{Action, KeyBindings},
+	gamepad::GamepadPlugin,
+	GameState,
+};
 
 #[derive(Debug, PartialEq)]
 pub enum Reset {
@@ -20,8 +24,12 @@ impl ResetPlugin {
 		mut writer: EventWriter<Reset>,
 		state: Res<State<GameState>>,
 		keyboard_input: Res<Input<KeyCode>>,
+		gamepad_input: Res<Input<GamepadButton>>,
+		bindings: Res<KeyBindings>,
 	) {
-		if state.0 == GameState::Playing && keyboard_input.just_pressed(KeyCode::R) {
+		let pressed = bindings.just_pressed(&keyboard_input, Action::Reset)
+			|| GamepadPlugin::just_pressed(&gamepad_input, GamepadButtonType::Select);
+		if state.0 == GameState::Playing && pressed {
 			writer.send(Reset::Hard);
 		}
 	}
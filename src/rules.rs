@@ -0,0 +1,211 @@
+use std::{str::FromStr, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{match_clock::MatchClock, player::Player, score::Score};
+
+/// Who serves after a goal.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ServeRule {
+	#[default]
+	Random,
+	/// Serve switches sides every given number of points, like in table tennis.
+	Alternate(usize),
+	/// The player who conceded the goal serves.
+	Loser,
+	/// The player who scored the goal serves.
+	Winner,
+}
+
+impl FromStr for ServeRule {
+	type Err = String;
+
+	/// Parses `random`, `loser`, `winner` or `alternate:<points>`.
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name.to_lowercase().split_once(':') {
+			Some(("alternate", points)) => points
+				.parse()
+				.map(ServeRule::Alternate)
+				.map_err(|_| format!("`{}` is not a number of points", points)),
+			None if name.eq_ignore_ascii_case("random") => Ok(ServeRule::Random),
+			None if name.eq_ignore_ascii_case("loser") => Ok(ServeRule::Loser),
+			None if name.eq_ignore_ascii_case("winner") => Ok(ServeRule::Winner),
+			_ => Err(format!(
+				"unknown serve rule `{}`, expected random, loser, winner or alternate:<points>",
+				name
+			)),
+		}
+	}
+}
+
+/// When a match is over, and who serves.
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct MatchRules {
+	/// Points needed to win the match.
+	pub target_score: usize,
+	/// How many points the winner has to be ahead by, e.g. `2` for win-by-two.
+	pub win_by: usize,
+	/// Ends the match after this many points have been played, as long as someone is ahead.
+	pub max_rallies: Option<usize>,
+	/// Ends the match when the [`MatchClock`] runs out, going into sudden death on a tie. In seconds.
+	#[serde(with = "seconds")]
+	pub time_limit: Option<Duration>,
+	pub serve: ServeRule,
+}
+
+impl Default for MatchRules {
+	fn default() -> Self {
+		Self {
+			target_score: 11,
+			win_by: 1,
+			max_rallies: None,
+			time_limit: None,
+			serve: ServeRule::default(),
+		}
+	}
+}
+
+impl MatchRules {
+	/// A match that lasts `time_limit`, however many points are scored.
+	pub fn timed(time_limit: Duration) -> Self {
+		Self {
+			target_score: usize::MAX,
+			time_limit: Some(time_limit),
+			..default()
+		}
+	}
+
+	/// Returns the player who won the match with `score`, or `None` if the match goes on.
+	pub fn winner(&self, score: &Score, clock: &MatchClock) -> Option<Player> {
+		let leader = score.leader()?;
+		if clock.sudden_death {
+			return Some(leader);
+		}
+
+		let (leading, trailing) = match leader {
+			Player::Left => (score.left, score.right),
+			Player::Right => (score.right, score.left),
+		};
+
+		if leading >= self.target_score && leading - trailing >= self.win_by.max(1) {
+			return Some(leader);
+		}
+
+		match self.max_rallies {
+			Some(max_rallies) if score.left + score.right >= max_rallies => Some(leader),
+			_ => None,
+		}
+	}
+}
+
+/// Reads and writes a time limit as a number of seconds, like the timings in [`crate::GameConfig`].
+mod seconds {
+	use std::time::Duration;
+
+	use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+	pub fn serialize<S: Serializer>(time_limit: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+		time_limit
+			.map(|time_limit| time_limit.as_secs_f64())
+			.serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+		Option::<f64>::deserialize(deserializer)?
+			.map(|seconds| Duration::try_from_secs_f64(seconds).map_err(D::Error::custom))
+			.transpose()
+	}
+}
+
+/// How the last match ended, available in [`crate::GameState::GameOver`].
+#[derive(Debug, Resource)]
+pub struct MatchResult {
+	pub winner: Player,
+	pub left: usize,
+	pub right: usize,
+}
+
+impl MatchResult {
+	pub fn new(winner: Player, score: &Score) -> Self {
+		Self {
+			winner,
+			left: score.left,
+			right: score.right,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn score(left: usize, right: usize) -> Score {
+		Score { left, right }
+	}
+
+	#[test]
+	fn serve_rules_are_parsed_by_name() {
+		assert_eq!("random".parse(), Ok(ServeRule::Random));
+		assert_eq!("Loser".parse(), Ok(ServeRule::Loser));
+		assert_eq!("WINNER".parse(), Ok(ServeRule::Winner));
+		assert_eq!("alternate:2".parse(), Ok(ServeRule::Alternate(2)));
+	}
+
+	#[test]
+	fn unknown_serve_rules_are_refused() {
+		let error = "alternate:two".parse::<ServeRule>().unwrap_err();
+		assert!(error.contains("`two`"), "{}", error);
+		let error = "alternate:-1".parse::<ServeRule>().unwrap_err();
+		assert!(error.contains("`-1`"), "{}", error);
+
+		let error = "coin".parse::<ServeRule>().unwrap_err();
+		assert!(error.contains("unknown serve rule `coin`"), "{}", error);
+		assert!("alternate".parse::<ServeRule>().is_err());
+		assert!("loser:2".parse::<ServeRule>().is_err());
+	}
+
+	#[test]
+	fn win_by_two_goes_on_past_the_target() {
+		let rules = MatchRules {
+			target_score: 11,
+			win_by: 2,
+			..default()
+		};
+		let clock = MatchClock::default();
+
+		assert_eq!(rules.winner(&score(10, 10), &clock), None);
+		assert_eq!(rules.winner(&score(11, 10), &clock), None);
+		assert_eq!(rules.winner(&score(11, 11), &clock), None);
+		assert_eq!(rules.winner(&score(12, 11), &clock), None);
+		assert_eq!(rules.winner(&score(12, 10), &clock), Some(Player::Left));
+		assert_eq!(rules.winner(&score(11, 13), &clock), Some(Player::Right));
+	}
+
+	#[test]
+	fn max_rallies_only_ends_a_match_with_a_leader() {
+		let rules = MatchRules {
+			max_rallies: Some(6),
+			..default()
+		};
+		let clock = MatchClock::default();
+
+		assert_eq!(rules.winner(&score(3, 2), &clock), None);
+		assert_eq!(rules.winner(&score(3, 3), &clock), None);
+		assert_eq!(rules.winner(&score(4, 4), &clock), None);
+		assert_eq!(rules.winner(&score(4, 5), &clock), Some(Player::Right));
+	}
+
+	#[test]
+	fn sudden_death_ends_on_the_next_goal() {
+		let rules = MatchRules::timed(Duration::from_secs(60));
+		let mut clock = MatchClock::new(&rules);
+		assert_eq!(rules.winner(&score(7, 3), &clock), None);
+
+		assert!(clock.tick(Duration::from_secs(60)));
+		clock.sudden_death = true;
+		assert_eq!(rules.winner(&score(5, 5), &clock), None);
+		assert_eq!(rules.winner(&score(6, 5), &clock), Some(Player::Left));
+	}
+}
@@ -1,17 +1,28 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use std::fmt::{Display, Formatter};
-use std::ops::DerefMut;
 
 use crate::GameSet;
-use crate::{reset::Reset, wall::Wall};
+use crate::{config::GameConfig, player::Player, simulation::Simulation};
 
-#[derive(Default, Resource)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Resource, Serialize)]
 pub struct Score {
 	pub left: usize,
 	pub right: usize,
 }
 
+impl Score {
+	/// The player with more points, or `None` on a tie.
+	pub fn leader(&self) -> Option<Player> {
+		match self.left.cmp(&self.right) {
+			std::cmp::Ordering::Greater => Some(Player::Left),
+			std::cmp::Ordering::Less => Some(Player::Right),
+			std::cmp::Ordering::Equal => None,
+		}
+	}
+}
+
 impl Display for Score {
 	fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
 		write!(formatter, "{:0>2}   {:0>2}", self.left, self.right)
@@ -21,18 +32,27 @@ impl Display for Score {
 #[derive(Default, Component)]
 pub struct ScoreBoard;
 
+impl ScoreBoard {
+	pub const FONT_SIZE: f32 = 100.;
+
+	/// How far from the top of the window the score's text ends, for text placed below it.
+	pub fn bottom(config: &GameConfig) -> f32 {
+		config.wall_width * 2.0 + Self::FONT_SIZE
+	}
+}
+
 pub struct ScorePlugin;
 impl Plugin for ScorePlugin {
 	fn build(&self, app: &mut App) {
 		app.add_startup_system(Self::setup)
-			.add_system(Self::handle_reset.in_set(GameSet::Reset))
-			.add_system(Self::update.in_set(GameSet::Movement))
+			.add_system(Self::sync.in_set(GameSet::Sync).in_schedule(CoreSchedule::FixedUpdate))
+			.add_system(Self::update)
 			.insert_resource(Score::default());
 	}
 }
 
 impl ScorePlugin {
-	pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+	pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, config: Res<GameConfig>) {
 		commands
 			.spawn((
 				Name::new("Scoreboard"),
@@ -42,7 +62,7 @@ impl ScorePlugin {
 						flex_direction: FlexDirection::Column,
 						justify_content: JustifyContent::FlexStart,
 						padding: UiRect {
-							top: Val::Px(Wall::WIDTH * 2.0),
+							top: Val::Px(config.wall_width * 2.0),
 							left: Val::Px(5.),
 							right: Val::Px(5.),
 							bottom: Val::Px(1.),
@@ -60,7 +80,7 @@ impl ScorePlugin {
 						Score::default().to_string(),
 						TextStyle {
 							color: Color::WHITE,
-							font_size: 100.,
+							font_size: ScoreBoard::FONT_SIZE,
 							font: asset_server.load("fonts/Pixelzim 3x5.ttf"),
 						},
 					)]),
@@ -68,12 +88,14 @@ impl ScorePlugin {
 			});
 	}
 
-	pub fn update(
-		mut reset_reader: EventReader<Reset>,
-		score: Res<Score>,
-		mut query: Query<&mut Text, With<ScoreBoard>>,
-	) {
-		if reset_reader.iter().last().is_none() {
+	pub fn sync(simulation: Res<Simulation>, mut score: ResMut<Score>) {
+		if *score != simulation.state.score {
+			*score = simulation.state.score;
+		}
+	}
+
+	pub fn update(score: Res<Score>, mut query: Query<&mut Text, With<ScoreBoard>>) {
+		if !score.is_changed() {
 			return;
 		}
 
@@ -83,23 +105,4 @@ impl ScorePlugin {
 			}
 		}
 	}
-
-	pub fn handle_reset(
-		mut reset_reader: EventReader<Reset>,
-		mut score: ResMut<Score>,
-		mut query: Query<&mut Text, With<ScoreBoard>>,
-	) {
-		if let Some(reset_event) = reset_reader.iter().last() {
-			if reset_event == &Reset::Hard {
-				score.deref_mut().left = 0;
-				score.deref_mut().right = 0;
-			}
-
-			for mut text in query.iter_mut() {
-				if let Some(section) = text.sections.get_mut(0) {
-					section.value = score.to_string();
-				}
-			}
-		}
-	}
 }
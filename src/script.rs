@@ -0,0 +1,260 @@
+//! Paddle input as a timeline of held keys, to be played back instead of the keyboard, e.g. by tests.
+
+use std::{
+	env,
+	path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	controller::{Controller, Controllers},
+	files::{self, FileError},
+	net::NetPlugin,
+	paddle::PaddleInput,
+	player::Player,
+	simulation::{Phase, Simulation, SimulationPlugin},
+};
+
+/// The keys a player holds during one step.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScriptKeys {
+	#[serde(default)]
+	pub up: bool,
+	#[serde(default)]
+	pub down: bool,
+	#[serde(default)]
+	pub serve: bool,
+}
+
+impl ScriptKeys {
+	pub const NONE: ScriptKeys = ScriptKeys {
+		up: false,
+		down: false,
+		serve: false,
+	};
+	pub const UP: ScriptKeys = ScriptKeys {
+		up: true,
+		..ScriptKeys::NONE
+	};
+	pub const DOWN: ScriptKeys = ScriptKeys {
+		down: true,
+		..ScriptKeys::NONE
+	};
+	pub const SERVE: ScriptKeys = ScriptKeys {
+		serve: true,
+		..ScriptKeys::NONE
+	};
+
+	/// The input the keyboard would give for these keys.
+	pub fn input(&self) -> PaddleInput {
+		let axis = if self.up {
+			1.
+		} else if self.down {
+			-1.
+		} else {
+			0.
+		};
+		PaddleInput {
+			axis,
+			serve: self.serve,
+		}
+	}
+
+	/// The keys closest to `input`, moving at all counts as holding the key.
+	pub fn from_input(input: &PaddleInput) -> Self {
+		Self {
+			up: input.axis > 0.,
+			down: input.axis < 0.,
+			serve: input.serve,
+		}
+	}
+}
+
+/// The same keys held for a number of steps.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScriptRun {
+	pub ticks: u64,
+	#[serde(flatten)]
+	pub keys: ScriptKeys,
+}
+
+/// The keys one player holds, step by step from the start of the match. Nothing is held once it runs out.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Timeline(Vec<ScriptRun>);
+
+impl Timeline {
+	/// Holds `keys` for the next `ticks` steps.
+	pub fn hold(mut self, ticks: u64, keys: ScriptKeys) -> Self {
+		match self.0.last_mut() {
+			Some(run) if run.keys == keys => run.ticks += ticks,
+			_ if ticks > 0 => self.0.push(ScriptRun { ticks, keys }),
+			_ => {}
+		}
+		self
+	}
+
+	/// Appends the keys of the next step.
+	pub fn push(&mut self, keys: ScriptKeys) {
+		*self = std::mem::take(self).hold(1, keys);
+	}
+
+	/// The keys held during the step after `tick` steps were taken.
+	pub fn keys(&self, tick: u64) -> ScriptKeys {
+		let mut start = 0;
+		for run in &self.0 {
+			start += run.ticks;
+			if tick < start {
+				return run.keys;
+			}
+		}
+		ScriptKeys::NONE
+	}
+
+	/// Number of steps until it runs out.
+	pub fn ticks(&self) -> u64 {
+		self.0.iter().map(|run| run.ticks).sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+/// A [`Timeline`] for each player. Paddles controlled by [`Controller::Script`] follow theirs.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct InputScript {
+	pub left: Timeline,
+	pub right: Timeline,
+}
+
+impl InputScript {
+	pub fn timeline(&self, player: Player) -> &Timeline {
+		match player {
+			Player::Left => &self.left,
+			Player::Right => &self.right,
+		}
+	}
+
+	pub fn timeline_mut(&mut self, player: Player) -> &mut Timeline {
+		match player {
+			Player::Left => &mut self.left,
+			Player::Right => &mut self.right,
+		}
+	}
+
+	pub fn save(&self, path: &Path) -> Result<(), FileError> {
+		files::write_json(path, self)
+	}
+
+	pub fn load(path: &Path) -> Result<Self, FileError> {
+		files::read_json(path)
+	}
+}
+
+/// Which script to play and where to capture input to, see [`ScriptPlugin`].
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ScriptConfig {
+	/// Plays this script for every side it holds keys for.
+	pub play: Option<PathBuf>,
+	/// Saves the input of every finished match here.
+	pub capture: Option<PathBuf>,
+}
+
+impl ScriptConfig {
+	/// Reads `PONG_SCRIPT` and `PONG_CAPTURE`.
+	pub fn from_env() -> Self {
+		Self {
+			play: env::var_os("PONG_SCRIPT").map(PathBuf::from),
+			capture: env::var_os("PONG_CAPTURE").map(PathBuf::from),
+		}
+	}
+}
+
+/// The input of the match being played and of the last one that ended, as [`InputScript`]s.
+#[derive(Debug, Default, Resource)]
+pub struct InputCapture {
+	pub(crate) recording: Option<InputScript>,
+	pub last: Option<InputScript>,
+}
+
+/// Plays the [`InputScript`] for [`Controller::Script`] paddles and captures every match's input into one.
+pub struct ScriptPlugin;
+impl Plugin for ScriptPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ScriptConfig>()
+			.init_resource::<InputScript>()
+			.init_resource::<InputCapture>()
+			.add_startup_system(Self::setup)
+			.add_system(
+				Self::capture
+					.after(SimulationPlugin::step)
+					.run_if(SimulationPlugin::is_simulating)
+					.run_if(NetPlugin::is_authoritative)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			);
+	}
+}
+
+impl ScriptPlugin {
+	fn setup(mut script: ResMut<InputScript>, mut controllers: ResMut<Controllers>, config: Res<ScriptConfig>) {
+		let Some(path) = &config.play else {
+			return;
+		};
+
+		match InputScript::load(path) {
+			Ok(loaded) => {
+				*script = loaded;
+				for player in Player::ALL {
+					if !script.timeline(player).is_empty() {
+						controllers.set(player, Controller::Script);
+					}
+				}
+				info!("Playing the input script {}", path.display());
+			}
+			Err(error) => error!("Failed to play {}: {}", path.display(), error),
+		}
+	}
+
+	/// Runs right after every [`SimulationPlugin::step`], with the inputs that step used.
+	fn capture(
+		mut capture: ResMut<InputCapture>,
+		simulation: Res<Simulation>,
+		config: Res<ScriptConfig>,
+		input_query: Query<(&PaddleInput, &Player)>,
+	) {
+		let state = &simulation.state;
+		if state.attract {
+			capture.recording = None;
+			return;
+		}
+
+		// A new match started, dropping a capture that was cut short by a reset
+		if state.tick == 1 {
+			capture.recording = Some(InputScript::default());
+		}
+
+		let Some(script) = capture.recording.as_mut() else {
+			return;
+		};
+		for (input, player) in input_query.iter() {
+			script.timeline_mut(*player).push(ScriptKeys::from_input(input));
+		}
+
+		let Phase::GameOver { .. } = state.phase else {
+			return;
+		};
+
+		let script = capture.recording.take();
+		if let (Some(path), Some(script)) = (&config.capture, &script) {
+			match script.save(path) {
+				Ok(()) => info!("Saved the match's input to {}", path.display()),
+				Err(error) => error!("Failed to save the match's input to {}: {}", path.display(), error),
+			}
+		}
+		capture.last = script;
+	}
+}
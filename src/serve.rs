@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	player::Player,
+	rules::ServeRule,
+	simulation::{SimRng, Simulation},
+	GameSet,
+};
+
+/// Who serves the next ball, see [`ServeRule`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+pub struct Server {
+	pub player: Player,
+	/// Points served in a row by `player`.
+	served: usize,
+}
+
+impl Default for Server {
+	fn default() -> Self {
+		Self::new(Player::Left)
+	}
+}
+
+impl Server {
+	/// Steepest angle a serve can leave the paddle at. Moving the paddle while serving picks the angle.
+	pub const MAX_ANGLE: f32 = PI / 4.0;
+
+	pub fn new(player: Player) -> Self {
+		Self { player, served: 0 }
+	}
+
+	/// Picks the server for the point after `scorer` scored.
+	pub fn after_goal(&mut self, scorer: Player, rule: ServeRule, rng: &mut SimRng) {
+		self.served += 1;
+		let next = match rule {
+			ServeRule::Random if rng.bool() => Player::Left,
+			ServeRule::Random => Player::Right,
+			ServeRule::Alternate(points) if self.served >= points.max(1) => self.player.opponent(),
+			ServeRule::Alternate(_) => self.player,
+			ServeRule::Loser => scorer.opponent(),
+			ServeRule::Winner => scorer,
+		};
+
+		if next != self.player {
+			*self = Self::new(next);
+		}
+	}
+}
+
+pub struct ServePlugin;
+impl Plugin for ServePlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Server>()
+			.add_system(Self::sync.in_set(GameSet::Sync).in_schedule(CoreSchedule::FixedUpdate));
+	}
+}
+
+impl ServePlugin {
+	fn sync(simulation: Res<Simulation>, mut server: ResMut<Server>) {
+		if *server != simulation.state.server {
+			*server = simulation.state.server.clone();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Who serves after each of `scorers` scored, starting with the left player.
+	fn servers(rule: ServeRule, scorers: &[Player]) -> Vec<Player> {
+		let mut server = Server::default();
+		let mut rng = SimRng::new(0);
+		scorers
+			.iter()
+			.map(|scorer| {
+				server.after_goal(*scorer, rule, &mut rng);
+				server.player
+			})
+			.collect()
+	}
+
+	#[test]
+	fn alternate_switches_every_given_number_of_points() {
+		use Player::{Left, Right};
+
+		assert_eq!(
+			servers(ServeRule::Alternate(2), &[Left, Right, Right, Left, Left]),
+			[Left, Right, Right, Left, Left]
+		);
+		assert_eq!(
+			servers(ServeRule::Alternate(3), &[Right; 6]),
+			[Left, Left, Right, Right, Right, Left]
+		);
+	}
+
+	#[test]
+	fn alternate_switches_every_point_at_least() {
+		use Player::{Left, Right};
+
+		assert_eq!(servers(ServeRule::Alternate(0), &[Left; 4]), [Right, Left, Right, Left]);
+		assert_eq!(
+			servers(ServeRule::Alternate(0), &[Left; 4]),
+			servers(ServeRule::Alternate(1), &[Left; 4])
+		);
+	}
+
+	#[test]
+	fn loser_and_winner_pick_a_side_of_the_goal() {
+		use Player::{Left, Right};
+
+		assert_eq!(servers(ServeRule::Loser, &[Left, Left, Right]), [Right, Right, Left]);
+		assert_eq!(servers(ServeRule::Winner, &[Left, Left, Right]), [Left, Left, Right]);
+	}
+}
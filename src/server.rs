@@ -0,0 +1,193 @@
+//! Hosting matches between two clients without a window, see the `pong-server` binary.
+
+use std::{
+	net::SocketAddr,
+	path::PathBuf,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+	app::{AppExit, ScheduleRunnerSettings},
+	input::InputPlugin,
+	log::LogPlugin,
+	prelude::*,
+};
+use serde::Serialize;
+
+use crate::{
+	ball::BallPhysics,
+	config::GameConfig,
+	files,
+	net::{Host, NetMode, NetPlugin, NetSettings},
+	player::Player,
+	replay::{Recorder, ReplayConfig},
+	reset::Reset,
+	rules::{MatchResult, MatchRules},
+	score::Score,
+	simulation::Simulation,
+	GameState, PongPlugin,
+};
+
+/// A server hosting one match after another, see [`MatchServer::app`].
+#[derive(Clone, Debug)]
+pub struct MatchServer {
+	/// Where players and spectators connect over UDP.
+	pub address: SocketAddr,
+	/// Where they can also connect over WebSocket, see [`NetSettings::websocket`].
+	pub websocket: Option<SocketAddr>,
+	/// Seeds all of the server's randomness, `None` for a random seed.
+	pub seed: Option<u64>,
+	pub rules: MatchRules,
+	pub physics: BallPhysics,
+	pub config: GameConfig,
+	/// Where replays are saved, `None` to not save them.
+	pub replays: Option<PathBuf>,
+	/// The file every finished match is appended to as a line of JSON, `None` to not write results.
+	pub results: Option<PathBuf>,
+	/// Seconds the final score is shown before the next match starts.
+	pub restart_delay: f32,
+}
+
+impl Default for MatchServer {
+	fn default() -> Self {
+		Self {
+			address: ([0, 0, 0, 0], 7878).into(),
+			websocket: None,
+			seed: None,
+			rules: MatchRules::default(),
+			physics: BallPhysics::default(),
+			config: GameConfig::default(),
+			replays: Some(PathBuf::from("replays")),
+			results: Some(PathBuf::from("results.jsonl")),
+			restart_delay: 5.,
+		}
+	}
+}
+
+/// A finished match, as written to [`MatchServer::results`].
+#[derive(Debug, Serialize)]
+pub struct ServerResult {
+	/// Seconds since the Unix epoch.
+	pub finished: u64,
+	pub seed: u64,
+	pub left: Option<SocketAddr>,
+	pub right: Option<SocketAddr>,
+	pub winner: Player,
+	pub score: Score,
+	pub ticks: u64,
+	/// Seconds of game time the match took.
+	pub duration: f32,
+	pub replay: Option<PathBuf>,
+}
+
+impl MatchServer {
+	/// An [`App`] that runs in real time and serves until it's stopped.
+	pub fn app(&self) -> App {
+		let mut app = App::new();
+		let time_step = Duration::from_secs_f32(self.config.time_step);
+		app.insert_resource(ScheduleRunnerSettings::run_loop(time_step))
+			.add_plugins(MinimalPlugins)
+			.add_plugin(LogPlugin::default())
+			.add_plugin(InputPlugin)
+			.insert_resource(NetMode::Server(self.address))
+			.insert_resource(NetSettings {
+				websocket: self.websocket,
+				..default()
+			})
+			.add_plugin(PongPlugin {
+				seed: self.seed,
+				headless: true,
+			})
+			.insert_resource(self.rules.clone())
+			.insert_resource(self.physics.clone())
+			.insert_resource(self.config.clone())
+			.insert_resource(ReplayConfig {
+				directory: self.replays.clone(),
+			})
+			.insert_resource(ServerConfig {
+				results: self.results.clone(),
+				restart_delay: self.restart_delay,
+			})
+			.add_plugin(ServerPlugin);
+		app
+	}
+}
+
+#[derive(Resource)]
+struct ServerConfig {
+	results: Option<PathBuf>,
+	restart_delay: f32,
+}
+
+struct ServerPlugin;
+impl Plugin for ServerPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_startup_system(Self::check_host.in_base_set(StartupSet::PostStartup))
+			.add_system(Self::write_result.in_schedule(OnEnter(GameState::GameOver)))
+			.add_system(Self::restart.run_if(NetPlugin::is_host));
+	}
+}
+
+impl ServerPlugin {
+	/// Stops the server if it couldn't open its socket, there's nothing else it could do.
+	fn check_host(host: Option<Res<Host>>, mut exit_writer: EventWriter<AppExit>) {
+		if host.is_none() {
+			exit_writer.send(AppExit);
+		}
+	}
+
+	fn write_result(
+		config: Res<ServerConfig>,
+		host: Res<Host>,
+		simulation: Res<Simulation>,
+		recorder: Res<Recorder>,
+		result: Option<Res<MatchResult>>,
+	) {
+		let (Some(path), Some(result)) = (&config.results, result) else {
+			return;
+		};
+
+		let state = &simulation.state;
+		let server_result = ServerResult {
+			finished: SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_or(0, |time| time.as_secs()),
+			seed: state.seed,
+			left: host.address(Player::Left),
+			right: host.address(Player::Right),
+			winner: result.winner,
+			score: state.score,
+			ticks: state.tick,
+			duration: state.tick as f32 * simulation.settings.config.time_step,
+			replay: recorder.last_path.clone(),
+		};
+		info!("{:?} won {}", server_result.winner, server_result.score);
+
+		if let Err(error) = files::append_json_line(path, &server_result) {
+			error!("Failed to write the result to {}: {}", path.display(), error);
+		}
+	}
+
+	/// Starts the next match a while after the last one ended, as long as both players are still there.
+	fn restart(
+		mut waited: Local<f32>,
+		mut reset_writer: EventWriter<Reset>,
+		mut next_state: ResMut<NextState<GameState>>,
+		config: Res<ServerConfig>,
+		host: Res<Host>,
+		state: Res<State<GameState>>,
+		time: Res<Time>,
+	) {
+		if state.0 != GameState::GameOver {
+			*waited = 0.;
+			return;
+		}
+
+		*waited += time.delta_seconds();
+		if *waited >= config.restart_delay && host.is_full() {
+			*waited = 0.;
+			reset_writer.send(Reset::Hard);
+			next_state.set(GameState::WaitBeforeRound);
+		}
+	}
+}
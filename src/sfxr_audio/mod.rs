@@ -1,6 +1,6 @@
 use bevy::{audio::AddAudioSource, prelude::*, reflect::TypeUuid};
 
-use crate::{ball::CollisionEvent, GameSet};
+use crate::{ball::CollisionEvent, GameSet, GameState};
 
 use self::{sfxr_audio_asset_loader::SfxrAudioAssetLoader, sfxr_decoder::SfxrDecoder};
 
@@ -54,17 +54,24 @@ impl SfxrAudioPlugin {
 		mut collision_events: EventReader<CollisionEvent>,
 		audio: Res<Audio<SfxrAudio>>,
 		audio_handles: Res<AudioHandles>,
+		state: Res<State<GameState>>,
 	) {
+		// The attract mode behind the menu plays silently
+		if state.0 == GameState::Menu {
+			collision_events.clear();
+			return;
+		}
+
 		if !collision_events.is_empty() {
 			for collision_event in collision_events.iter() {
 				match collision_event {
-					CollisionEvent::Paddle => {
+					CollisionEvent::Paddle { .. } => {
 						audio.play(audio_handles.pong.clone());
 					}
-					CollisionEvent::Wall => {
+					CollisionEvent::Wall { .. } => {
 						audio.play(audio_handles.ping.clone());
 					}
-					CollisionEvent::Goal => {
+					CollisionEvent::Goal { .. } => {
 						audio.play(audio_handles.goal.clone());
 					}
 				};
@@ -0,0 +1,448 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	ball::{BallPhysics, BallPlugin, BallState, CollisionEvent, Rally},
+	collider::sweep,
+	config::GameConfig,
+	match_clock::MatchClock,
+	paddle::{Paddle, PaddleInput},
+	player::Player,
+	power_up::PowerUps,
+	rules::MatchRules,
+	score::Score,
+	serve::Server,
+	wall::Wall,
+	GameState,
+};
+
+mod plugin;
+mod rng;
+
+pub use plugin::SimulationPlugin;
+pub use rng::SimRng;
+
+/// Upper bound on the bounces resolved per step, so a ball wedged between colliders can't stall the step.
+const MAX_BOUNCES_PER_STEP: usize = 4;
+
+/// Everything that stays the same during a match, except for config changes while playing offline.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SimSettings {
+	pub rules: MatchRules,
+	pub physics: BallPhysics,
+	/// Flattened, so replays saved before it existed still load with their `time_step` and `wait_after_goal`.
+	#[serde(flatten)]
+	pub config: GameConfig,
+}
+
+/// A paddle as seen by the simulation. Its x coordinate is fixed, see [`Paddle::x`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PaddleState {
+	pub y: f32,
+	pub velocity: f32,
+	pub speed: f32,
+	/// Factor [`GameConfig::paddle_height`] is multiplied by, `1.` unless a power-up changed it.
+	pub scale: f32,
+}
+
+impl PaddleState {
+	/// A paddle standing still in the middle.
+	pub fn new(speed: f32) -> Self {
+		Self {
+			y: 0.,
+			velocity: 0.,
+			speed,
+			scale: 1.,
+		}
+	}
+
+	pub fn height(&self, config: &GameConfig) -> f32 {
+		config.paddle_height * self.scale
+	}
+}
+
+/// The simulation's counterpart to [`GameState`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Phase {
+	WaitBeforeRound { ticks_left: u32 },
+	Serve,
+	Playing,
+	GameOver { winner: Player },
+}
+
+impl Phase {
+	pub fn game_state(&self) -> GameState {
+		match self {
+			Phase::WaitBeforeRound { .. } => GameState::WaitBeforeRound,
+			Phase::Serve => GameState::Serve,
+			Phase::Playing => GameState::Playing,
+			Phase::GameOver { .. } => GameState::GameOver,
+		}
+	}
+}
+
+/// Something that happened during a [`SimState::step`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimEvent {
+	Collision(CollisionEvent),
+	/// The ball went back to the centre after a goal.
+	BallReset,
+	PhaseChanged(Phase),
+}
+
+/// The complete state of a match. Stepping two equal states with the same inputs gives equal states.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SimState {
+	pub seed: u64,
+	/// Steps taken since the match started.
+	pub tick: u64,
+	/// Set for the bot match playing behind the menu, where goals don't count and the match never ends.
+	pub attract: bool,
+	pub phase: Phase,
+	pub ball: BallState,
+	/// Indexed by [`Player::index`].
+	pub paddles: [PaddleState; 2],
+	pub score: Score,
+	pub rally: Rally,
+	pub server: Server,
+	pub clock: MatchClock,
+	pub power_ups: PowerUps,
+	rng: SimRng,
+}
+
+impl SimState {
+	pub fn new(settings: &SimSettings, seed: u64, attract: bool) -> Self {
+		let mut rng = SimRng::new(seed);
+		// Toss a coin for the first serve of a match
+		let server = Server::new(Self::random_player(&mut rng));
+		let mut ball = BallState::new(Self::random_direction(&mut rng), settings.config.ball_speed);
+
+		let phase = if attract {
+			Phase::Playing
+		} else {
+			ball.hold();
+			Phase::WaitBeforeRound {
+				ticks_left: Self::wait_ticks(settings),
+			}
+		};
+
+		Self {
+			seed,
+			tick: 0,
+			attract,
+			phase,
+			ball,
+			paddles: [PaddleState::new(settings.config.paddle_speed); 2],
+			score: Score::default(),
+			rally: Rally::default(),
+			server,
+			clock: MatchClock::new(&settings.rules),
+			power_ups: settings
+				.physics
+				.power_ups
+				.as_ref()
+				.map_or_else(PowerUps::default, |power_ups| {
+					PowerUps::new(power_ups, &settings.config)
+				}),
+			rng,
+		}
+	}
+
+	/// Advances the match by [`GameConfig::time_step`], with `inputs` indexed by [`Player::index`].
+	pub fn step(&mut self, settings: &SimSettings, inputs: &[PaddleInput; 2]) -> Vec<SimEvent> {
+		let mut events = Vec::new();
+		self.tick += 1;
+
+		let config = &settings.config;
+		for (paddle, input) in self.paddles.iter_mut().zip(inputs) {
+			let max_y = Paddle::max_y(paddle.height(config), config);
+			paddle.velocity = input.axis.clamp(-1., 1.) * paddle.speed;
+			paddle.y = (paddle.y + paddle.velocity * config.time_step).clamp(-max_y, max_y);
+		}
+
+		match self.phase {
+			Phase::WaitBeforeRound { ticks_left } if ticks_left > 1 => {
+				self.phase = Phase::WaitBeforeRound {
+					ticks_left: ticks_left - 1,
+				};
+			}
+			Phase::WaitBeforeRound { .. } => self.set_phase(Phase::Serve, &mut events),
+			Phase::Serve => self.serve(config, inputs[self.server.player.index()].serve, &mut events),
+			Phase::Playing => {
+				self.move_ball(settings, &mut events);
+				if self.phase == Phase::Playing {
+					if let Some(power_ups) = &settings.physics.power_ups {
+						self.power_ups
+							.step(power_ups, config, &self.ball, &mut self.paddles, &mut self.rng);
+					}
+					self.tick_clock(settings, &mut events);
+				}
+			}
+			Phase::GameOver { .. } => {}
+		}
+
+		events
+	}
+
+	fn set_phase(&mut self, phase: Phase, events: &mut Vec<SimEvent>) {
+		self.phase = phase;
+		events.push(SimEvent::PhaseChanged(phase));
+	}
+
+	/// Keeps the ball in front of the serving paddle and launches it once the server asks for it.
+	fn serve(&mut self, config: &GameConfig, launch: bool, events: &mut Vec<SimEvent>) {
+		let player = self.server.player;
+		let paddle = self.paddles[player.index()];
+		let direction_x = match player {
+			Player::Left => 1.,
+			Player::Right => -1.,
+		};
+
+		// Half the paddle and half the ball, which are both as wide as the walls. Leave a pixel of room so the ball
+		// doesn't start out touching the paddle
+		let offset = config.wall_width + 1.;
+		self.ball.hold();
+		self.ball.position = Vec2::new(Paddle::x(player, config) + direction_x * offset, paddle.y);
+
+		if launch {
+			let angle = (paddle.velocity / paddle.speed).clamp(-1., 1.) * Server::MAX_ANGLE;
+			self.ball
+				.launch(Vec2::new(direction_x * angle.cos(), angle.sin()), config.ball_speed);
+			self.power_ups.last_hit = Some(player);
+			self.set_phase(Phase::Playing, events);
+		}
+	}
+
+	/// Moves the ball for one step, bouncing off every wall and paddle it hits on the way.
+	///
+	/// Collisions are found by sweeping the ball along its path, so it can't tunnel through paddles or walls
+	/// no matter how fast it is, and bounces as often as needed within a step.
+	fn move_ball(&mut self, settings: &SimSettings, events: &mut Vec<SimEvent>) {
+		let physics = &settings.physics;
+		let config = &settings.config;
+		let max_angle = config.max_bounce_angle_radians();
+		let mut remaining = config.time_step;
+		if let Some(spin) = &physics.spin {
+			self.ball.curve(spin, remaining, max_angle);
+		}
+
+		for _ in 0..MAX_BOUNCES_PER_STEP {
+			let ball_box = self.ball.aabb(config.wall_width);
+			let motion = self.ball.velocity() * remaining;
+
+			let walls = Wall::ALL.into_iter().map(|wall| (wall.aabb(config), Some(wall), None));
+			let paddles = Player::ALL.into_iter().map(|player| {
+				(
+					Paddle::aabb(player, &self.paddles[player.index()], config),
+					None,
+					Some(player),
+				)
+			});
+
+			let nearest = walls
+				.chain(paddles)
+				.filter_map(|(collider_box, wall, player)| {
+					sweep(&ball_box, motion, &collider_box).map(|hit| (hit, collider_box, wall, player))
+				})
+				.min_by(|(a, ..), (b, ..)| a.time.total_cmp(&b.time));
+
+			let Some((hit, collider_box, wall, player)) = nearest else {
+				self.ball.position += motion;
+				break;
+			};
+
+			self.ball.position += motion * hit.time;
+			remaining *= 1. - hit.time;
+
+			if let Some(player) = player {
+				debug!("Paddle collision at {:?}", hit.contact);
+
+				if hit.normal.x != 0. {
+					let ball_angle = BallPlugin::calculate_bounce_angle(
+						&collider_box.centre.extend(0.),
+						&self.ball.position.extend(0.),
+						collider_box.half_size.y * 2.,
+						config,
+					);
+					self.ball.direction = Vec2::new(hit.normal.x * ball_angle.x, -ball_angle.y);
+				} else {
+					// Hit the paddle's top or bottom, it's going to be a goal anyway
+					self.ball.direction.y = -self.ball.direction.y;
+				}
+
+				self.ball.speed = (self.ball.speed * physics.speed_up).min(physics.max_speed);
+				if let Some(spin) = &physics.spin {
+					self.ball.apply_spin(&self.paddles[player.index()], spin, max_angle);
+				}
+				self.power_ups.last_hit = Some(player);
+				self.rally.hits += 1;
+				self.rally.longest = self.rally.longest.max(self.rally.hits);
+
+				events.push(SimEvent::Collision(CollisionEvent::Paddle { contact: hit.contact }));
+				continue;
+			}
+
+			if hit.normal.x != 0. {
+				self.ball.direction.x = -self.ball.direction.x;
+			} else {
+				self.ball.direction.y = -self.ball.direction.y;
+			}
+
+			match wall {
+				Some(Wall::Top | Wall::Bottom) => {
+					events.push(SimEvent::Collision(CollisionEvent::Wall { contact: hit.contact }));
+				}
+				Some(wall @ (Wall::Right | Wall::Left)) => {
+					let scorer = if wall == Wall::Right {
+						Player::Left
+					} else {
+						Player::Right
+					};
+					events.push(SimEvent::Collision(CollisionEvent::Goal {
+						scorer,
+						contact: hit.contact,
+					}));
+					self.goal(scorer, settings, events);
+					break;
+				}
+				None => {}
+			}
+		}
+	}
+
+	fn goal(&mut self, scorer: Player, settings: &SimSettings, events: &mut Vec<SimEvent>) {
+		self.rally.hits = 0;
+		self.ball = BallState::new(Self::random_direction(&mut self.rng), settings.config.ball_speed);
+		events.push(SimEvent::BallReset);
+		// Power-ups last until the next goal at most
+		if let Some(power_ups) = &settings.physics.power_ups {
+			self.power_ups = PowerUps::new(power_ups, &settings.config);
+			self.power_ups.apply(power_ups, &settings.config, &mut self.paddles);
+		}
+
+		// Goals in the attract mode behind the menu don't count
+		if self.attract {
+			return;
+		}
+
+		match scorer {
+			Player::Left => self.score.left += 1,
+			Player::Right => self.score.right += 1,
+		}
+		self.server.after_goal(scorer, settings.rules.serve, &mut self.rng);
+		self.ball.hold();
+
+		match settings.rules.winner(&self.score, &self.clock) {
+			Some(winner) => self.set_phase(Phase::GameOver { winner }, events),
+			None => self.set_phase(
+				Phase::WaitBeforeRound {
+					ticks_left: Self::wait_ticks(settings),
+				},
+				events,
+			),
+		}
+	}
+
+	fn tick_clock(&mut self, settings: &SimSettings, events: &mut Vec<SimEvent>) {
+		if self.attract || !self.clock.tick(Duration::from_secs_f32(settings.config.time_step)) {
+			return;
+		}
+
+		match self.score.leader() {
+			Some(winner) => self.set_phase(Phase::GameOver { winner }, events),
+			None => {
+				debug!("Time is up on a tie, going into sudden death");
+				self.clock.sudden_death = true;
+			}
+		}
+	}
+
+	fn wait_ticks(settings: &SimSettings) -> u32 {
+		let config = &settings.config;
+		(config.wait_after_goal / config.time_step).round().max(1.) as u32
+	}
+
+	fn random_player(rng: &mut SimRng) -> Player {
+		if rng.bool() {
+			Player::Left
+		} else {
+			Player::Right
+		}
+	}
+
+	fn random_direction(rng: &mut SimRng) -> Vec2 {
+		Vec2::new(if rng.bool() { 1. } else { -1. }, if rng.bool() { 0.5 } else { -0.5 })
+	}
+}
+
+/// The match the game is showing, wrapped by [`BallPlugin`], [`crate::paddle::PaddlePlugin`],
+/// [`crate::score::ScorePlugin`] and friends, see [`SimulationPlugin`].
+#[derive(Debug, Resource)]
+pub struct Simulation {
+	pub settings: SimSettings,
+	pub state: SimState,
+}
+
+impl Simulation {
+	pub fn new(settings: SimSettings, seed: u64, attract: bool) -> Self {
+		let state = SimState::new(&settings, seed, attract);
+		Self { settings, state }
+	}
+
+	pub fn step(&mut self, inputs: &[PaddleInput; 2]) -> Vec<SimEvent> {
+		self.state.step(&self.settings, inputs)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::WINDOW_HEIGHT;
+
+	/// Where the ball's centre turns around at the top and bottom walls with the default config.
+	const MAX_Y: f32 = WINDOW_HEIGHT / 2. - 30.;
+
+	/// A ball in the middle of the field moving straight up, covering `distance` pixels per step.
+	fn bouncing(settings: &SimSettings, distance: f32) -> SimState {
+		let mut state = SimState::new(settings, 0, true);
+		state.ball = BallState::new(Vec2::Y, distance / settings.config.time_step);
+		state
+	}
+
+	fn wall_hits(events: &[SimEvent]) -> usize {
+		events
+			.iter()
+			.filter(|event| matches!(event, SimEvent::Collision(CollisionEvent::Wall { .. })))
+			.count()
+	}
+
+	#[test]
+	fn the_ball_bounces_more_than_once_per_step() {
+		let settings = SimSettings::default();
+		// Up to the top, all the way down to the bottom and 100 pixels back up
+		let mut state = bouncing(&settings, 3. * MAX_Y + 100.);
+
+		let events = state.step(&settings, &[PaddleInput::default(); 2]);
+
+		assert_eq!(wall_hits(&events), 2, "{:?}", events);
+		assert!(
+			(state.ball.position.y - (-MAX_Y + 100.)).abs() < 1e-2,
+			"{:?}",
+			state.ball
+		);
+		assert!(state.ball.direction.y > 0.);
+	}
+
+	#[test]
+	fn bounces_per_step_are_limited() {
+		let settings = SimSettings::default();
+		let mut state = bouncing(&settings, 20. * MAX_Y);
+
+		let events = state.step(&settings, &[PaddleInput::default(); 2]);
+
+		assert_eq!(wall_hits(&events), MAX_BOUNCES_PER_STEP, "{:?}", events);
+		assert!(state.ball.position.y.abs() <= MAX_Y + 1e-2, "{:?}", state.ball);
+	}
+}
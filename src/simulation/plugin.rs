@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_turborand::*;
+
+use crate::{
+	ball::{BallPhysics, CollisionEvent},
+	config::GameConfig,
+	net::NetPlugin,
+	paddle::PaddleInput,
+	player::Player,
+	reset::Reset,
+	rules::{MatchResult, MatchRules},
+	GameSet, GameState,
+};
+
+use super::{Phase, SimEvent, SimSettings, Simulation};
+
+/// Steps the [`Simulation`] once per fixed update and turns what happened into events and state changes.
+pub struct SimulationPlugin;
+impl Plugin for SimulationPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_startup_system(Self::setup)
+			.add_system(
+				Self::handle_reset
+					.in_set(GameSet::Reset)
+					.run_if(NetPlugin::is_authoritative),
+			)
+			.add_system(
+				Self::step
+					.in_set(GameSet::Movement)
+					.run_if(Self::is_simulating)
+					.run_if(NetPlugin::is_authoritative)
+					.in_schedule(CoreSchedule::FixedUpdate),
+			)
+			.add_system(Self::sync_time_step);
+	}
+}
+
+impl SimulationPlugin {
+	fn setup(
+		mut commands: Commands,
+		mut rng: ResMut<GlobalRng>,
+		rules: Res<MatchRules>,
+		physics: Res<BallPhysics>,
+		config: Res<GameConfig>,
+	) {
+		let settings = SimSettings {
+			rules: rules.clone(),
+			physics: physics.clone(),
+			config: config.clone(),
+		};
+		// The game starts in the menu, with bots playing in the background
+		commands.insert_resource(Simulation::new(settings, rng.u64(..), true));
+	}
+
+	/// Whether the simulation advances in the current [`GameState`].
+	pub fn is_simulating(state: Res<State<GameState>>) -> bool {
+		matches!(
+			state.0,
+			GameState::Menu | GameState::WaitBeforeRound | GameState::Serve | GameState::Playing
+		)
+	}
+
+	/// Starts a new match with a fresh seed on every [`Reset::Hard`], picking up changed rules, physics and config.
+	#[allow(clippy::too_many_arguments)]
+	fn handle_reset(
+		mut simulation: ResMut<Simulation>,
+		mut reset_reader: EventReader<Reset>,
+		mut rng: ResMut<GlobalRng>,
+		mut next_state: ResMut<NextState<GameState>>,
+		rules: Res<MatchRules>,
+		physics: Res<BallPhysics>,
+		config: Res<GameConfig>,
+		state: Res<State<GameState>>,
+	) {
+		if !reset_reader.iter().any(|reset| *reset == Reset::Hard) {
+			return;
+		}
+
+		let attract = next_state.0.unwrap_or(state.0) == GameState::Menu;
+		let settings = SimSettings {
+			rules: rules.clone(),
+			physics: physics.clone(),
+			config: config.clone(),
+		};
+		*simulation = Simulation::new(settings, rng.u64(..), attract);
+
+		if !attract {
+			next_state.set(simulation.state.phase.game_state());
+		}
+	}
+
+	/// Runs the fixed update as often as the config of the current match asks for.
+	fn sync_time_step(simulation: Res<Simulation>, mut fixed_time: ResMut<FixedTime>) {
+		let period = Duration::from_secs_f32(simulation.settings.config.time_step);
+		if fixed_time.period != period {
+			fixed_time.period = period;
+		}
+	}
+
+	pub fn step(
+		mut simulation: ResMut<Simulation>,
+		mut commands: Commands,
+		mut collision_events: EventWriter<CollisionEvent>,
+		mut reset_writer: EventWriter<Reset>,
+		mut next_state: ResMut<NextState<GameState>>,
+		input_query: Query<(&PaddleInput, &Player)>,
+	) {
+		for event in simulation.step(&Self::inputs(&input_query)) {
+			match event {
+				SimEvent::Collision(collision) => collision_events.send(collision),
+				SimEvent::BallReset => reset_writer.send(Reset::Soft),
+				SimEvent::PhaseChanged(_) if simulation.state.attract => {}
+				SimEvent::PhaseChanged(phase) => {
+					if let Phase::GameOver { winner } = phase {
+						commands.insert_resource(MatchResult::new(winner, &simulation.state.score));
+					}
+					next_state.set(phase.game_state());
+				}
+			}
+		}
+	}
+
+	/// The [`PaddleInput`] of every paddle, indexed by [`Player::index`].
+	pub fn inputs(input_query: &Query<(&PaddleInput, &Player)>) -> [PaddleInput; 2] {
+		let mut inputs = [PaddleInput::default(); 2];
+		for (input, player) in input_query.iter() {
+			inputs[player.index()] = *input;
+		}
+		inputs
+	}
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A tiny seeded random number generator (wyrand), owned by the [`super::SimState`]
+/// so that a simulation only depends on its seed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SimRng(u64);
+
+impl SimRng {
+	pub fn new(seed: u64) -> Self {
+		Self(seed)
+	}
+
+	pub fn u64(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0xa076_1d64_78bd_642f);
+		let mixed = u128::from(self.0) * u128::from(self.0 ^ 0xe703_7ed1_a0b4_28db);
+		(mixed as u64) ^ ((mixed >> 64) as u64)
+	}
+
+	pub fn bool(&mut self) -> bool {
+		self.u64() & 1 == 1
+	}
+
+	/// A number from `0.` up to, but not including, `1.`.
+	pub fn f32(&mut self) -> f32 {
+		// 24 bits is all the precision an f32 has
+		(self.u64() >> 40) as f32 / (1u64 << 24) as f32
+	}
+
+	/// A number from `-1.` to `1.`.
+	pub fn f32_normalized(&mut self) -> f32 {
+		self.f32() * 2. - 1.
+	}
+}
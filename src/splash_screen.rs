@@ -3,30 +3,51 @@ use bevy::{
 	prelude::*,
 };
 
-use crate::GameState;
+use crate::{
+	ball::BallPhysics,
+	config::GameConfig,
+	controls::{Action, ControlsScreenPlugin, KeyBindings},
+	preset::{Preset, PresetConfig, Presets},
+	reset::Reset,
+	rules::MatchRules,
+	GameState,
+};
 
 #[derive(Component)]
 pub struct SplashScreen;
 
+/// The text naming the selected [`Preset`].
+#[derive(Component)]
+struct PresetText;
+
 pub struct SplashScreenPlugin;
 impl Plugin for SplashScreenPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_system(Self::cleanup.in_schedule(OnExit(GameState::Menu)))
 			.add_system(Self::setup.in_schedule(OnEnter(GameState::Menu)))
+			.add_systems((Self::handle_presets, Self::update_preset).in_set(OnUpdate(GameState::Menu)))
 			.add_system(Self::toggle_splash);
 	}
 }
 
 impl SplashScreenPlugin {
-	pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+	/// Selects the next [`Preset`].
+	pub const NEXT_PRESET_KEY: KeyCode = KeyCode::Tab;
+	/// Saves the rules, physics and config being played as the custom preset.
+	pub const SAVE_PRESET_KEY: KeyCode = KeyCode::F5;
+	const CUSTOM_PRESET: &'static str = "Custom";
+
+	pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, presets: Res<Presets>) {
+		let font = asset_server.load("fonts/Pixelzim 3x5.ttf");
 		commands
 			.spawn((
 				Name::new("Splash Screen"),
 				SplashScreen,
 				NodeBundle {
-					background_color: BackgroundColor(Color::rgba(0., 0., 0., 0.9)),
+					background_color: BackgroundColor(Color::rgba(0., 0., 0., 0.6)),
 					style: Style {
 						align_items: AlignItems::Center,
+						flex_direction: FlexDirection::Column,
 						justify_content: JustifyContent::Center,
 						position_type: PositionType::Absolute,
 						size: Size::new(Val::Percent(100.), Val::Percent(100.)),
@@ -43,28 +64,120 @@ impl SplashScreenPlugin {
 						TextStyle {
 							color: Color::WHITE,
 							font_size: 100.0,
-							font: asset_server.load("fonts/Pixelzim 3x5.ttf"),
+							font: font.clone(),
+						},
+					),
+				));
+				commands.spawn((
+					Name::new("Splash Screen Preset"),
+					PresetText,
+					TextBundle::from_section(
+						Self::preset_text(presets.selected()),
+						TextStyle {
+							color: Color::WHITE,
+							font_size: 50.0,
+							font: font.clone(),
+						},
+					),
+				));
+				commands.spawn((
+					Name::new("Splash Screen Options"),
+					TextBundle::from_section(
+						format!(
+							"{:?}: save as custom   {:?}: controls",
+							Self::SAVE_PRESET_KEY,
+							ControlsScreenPlugin::OPEN_KEY
+						),
+						TextStyle {
+							color: Color::WHITE,
+							font_size: 50.0,
+							font,
 						},
 					),
 				));
 			});
 	}
 
+	fn preset_text(preset: &Preset) -> String {
+		format!("{:?}: {}", Self::NEXT_PRESET_KEY, preset.name)
+	}
+
+	fn handle_presets(
+		mut presets: ResMut<Presets>,
+		keyboard_input: Res<Input<KeyCode>>,
+		preset_config: Res<PresetConfig>,
+		rules: Res<MatchRules>,
+		physics: Res<BallPhysics>,
+		config: Res<GameConfig>,
+	) {
+		if keyboard_input.just_pressed(Self::NEXT_PRESET_KEY) {
+			presets.select_next();
+		}
+		if !keyboard_input.just_pressed(Self::SAVE_PRESET_KEY) {
+			return;
+		}
+
+		// Keeps whatever was tuned in the config file, too
+		let preset = Preset {
+			name: Self::CUSTOM_PRESET.to_owned(),
+			rules: rules.clone(),
+			physics: physics.clone(),
+			config: Some(config.clone()),
+		};
+		if let Some(directory) = &preset_config.directory {
+			match preset.save(directory) {
+				Ok(path) => info!("Saved the custom preset to {}", path.display()),
+				Err(error) => error!("Failed to save the custom preset to {}: {}", directory.display(), error),
+			}
+		}
+		let index = presets.add(preset);
+		presets.select(index);
+	}
+
+	fn update_preset(presets: Res<Presets>, mut query: Query<&mut Text, With<PresetText>>) {
+		if !presets.is_changed() {
+			return;
+		}
+
+		for mut text in query.iter_mut() {
+			text.sections[0].value = Self::preset_text(presets.selected());
+		}
+	}
+
 	pub fn cleanup(mut commands: Commands, mut query: Query<Entity, With<SplashScreen>>) {
 		for entity in query.iter_mut() {
 			commands.entity(entity).despawn_recursive();
 		}
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	pub fn toggle_splash(
 		state: Res<State<GameState>>,
 		mut next_state: ResMut<NextState<GameState>>,
 		mut keyboard_input_event_reader: EventReader<KeyboardInput>,
+		gamepad_input: Res<Input<GamepadButton>>,
+		mouse_input: Res<Input<MouseButton>>,
+		touches: Res<Touches>,
+		mut reset_writer: EventWriter<Reset>,
+		bindings: Res<KeyBindings>,
 	) {
-		for event in keyboard_input_event_reader.iter() {
-			if state.0 == GameState::Menu && event.state == ButtonState::Pressed {
-				next_state.set(GameState::WaitBeforeRound);
-			}
+		// Those keys pick a preset, open the controls screen or quit instead
+		let ignored = [
+			Some(Self::NEXT_PRESET_KEY),
+			Some(Self::SAVE_PRESET_KEY),
+			Some(ControlsScreenPlugin::OPEN_KEY),
+			Some(bindings.key(Action::Quit)),
+		];
+		let key_pressed = keyboard_input_event_reader
+			.iter()
+			.any(|event| event.state == ButtonState::Pressed && !ignored.contains(&event.key_code));
+		let button_pressed = gamepad_input.get_just_pressed().next().is_some()
+			|| mouse_input.get_just_pressed().next().is_some()
+			|| touches.any_just_pressed();
+		if state.0 == GameState::Menu && (key_pressed || button_pressed) {
+			// Clear whatever the attract mode left behind before the real match starts
+			reset_writer.send(Reset::Hard);
+			next_state.set(GameState::WaitBeforeRound);
 		}
 	}
 }
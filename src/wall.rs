@@ -1,8 +1,13 @@
 use bevy::prelude::*;
 
-use crate::{collider::Collider, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::{
+	collider::{Aabb, Collider},
+	config::GameConfig,
+	simulation::Simulation,
+	WINDOW_HEIGHT, WINDOW_WIDTH,
+};
 
-#[derive(Component, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Component, Debug, Eq, PartialEq)]
 pub enum Wall {
 	Top,
 	Bottom,
@@ -11,38 +16,63 @@ pub enum Wall {
 }
 
 impl Wall {
-	pub const WIDTH: f32 = 20.;
+	pub const ALL: [Wall; 4] = [Wall::Top, Wall::Right, Wall::Bottom, Wall::Left];
+
+	/// The wall's box, [`GameConfig::wall_width`] thick along the edge of the window.
+	pub fn aabb(&self, config: &GameConfig) -> Aabb {
+		let width = config.wall_width;
+		let wall_y = (WINDOW_HEIGHT - width) / 2.;
+		let wall_x = (WINDOW_WIDTH - width) / 2.;
+		let centre = match self {
+			Wall::Top => Vec2::new(0., wall_y),
+			Wall::Bottom => Vec2::new(0., -wall_y),
+			Wall::Right => Vec2::new(wall_x, 0.),
+			Wall::Left => Vec2::new(-wall_x, 0.),
+		};
+
+		let size = match self {
+			Wall::Top | Wall::Bottom => Vec2::new(WINDOW_WIDTH, width),
+			Wall::Right | Wall::Left => Vec2::new(width, WINDOW_HEIGHT),
+		};
+
+		Aabb::new(centre, size)
+	}
 }
 
 pub struct WallPlugin;
 impl Plugin for WallPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_startup_system(Self::setup);
+		app.add_startup_system(Self::setup).add_system(Self::resize);
 	}
 }
 
 impl WallPlugin {
-	pub fn setup(mut commands: Commands) {
-		Self::spawn_wall(Wall::Top, &mut commands);
-		Self::spawn_wall(Wall::Right, &mut commands);
-		Self::spawn_wall(Wall::Bottom, &mut commands);
-		Self::spawn_wall(Wall::Left, &mut commands);
+	pub fn setup(mut commands: Commands, config: Res<GameConfig>) {
+		for wall in Wall::ALL {
+			Self::spawn_wall(wall, &mut commands, &config);
+		}
 	}
 
-	fn spawn_wall(wall: Wall, commands: &mut Commands) {
-		let wall_y = (WINDOW_HEIGHT - Wall::WIDTH) / 2.;
-		let wall_x = (WINDOW_WIDTH - Wall::WIDTH) / 2.;
-		let translation = match wall {
-			Wall::Top => Vec3::new(0., wall_y, 0.),
-			Wall::Bottom => Vec3::new(0., -wall_y, 0.),
-			Wall::Right => Vec3::new(wall_x, 0., 0.),
-			Wall::Left => Vec3::new(-wall_x, 0., 0.),
-		};
+	/// Follows config changes to the wall width.
+	fn resize(simulation: Res<Simulation>, mut query: Query<(&mut Sprite, &mut Transform, &Wall)>) {
+		if !simulation.is_changed() {
+			return;
+		}
 
-		let custom_size = match wall {
-			Wall::Top | Wall::Bottom => Vec2::new(WINDOW_WIDTH, Wall::WIDTH),
-			Wall::Right | Wall::Left => Vec2::new(Wall::WIDTH, WINDOW_HEIGHT),
-		};
+		for (mut sprite, mut transform, wall) in query.iter_mut() {
+			let aabb = wall.aabb(&simulation.settings.config);
+			let custom_size = Some(aabb.half_size * 2.);
+			if sprite.custom_size != custom_size {
+				sprite.custom_size = custom_size;
+				transform.translation = aabb.centre.extend(0.);
+			}
+		}
+	}
+
+	fn spawn_wall(wall: Wall, commands: &mut Commands, config: &GameConfig) {
+		let aabb = wall.aabb(config);
+		let translation = aabb.centre.extend(0.);
+		let custom_size = aabb.half_size * 2.;
 
 		commands.spawn((
 			Name::new(format!("Wall {:?}", wall)),
@@ -0,0 +1,85 @@
+//! The command-line options the binaries share.
+
+use std::time::Duration;
+
+use bevy_pong::{cli::PresetArgs, preset::Preset, ServeRule};
+use clap::Parser;
+
+#[derive(Parser)]
+struct Args {
+	#[command(flatten)]
+	preset: PresetArgs,
+}
+
+fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+	Args::try_parse_from(std::iter::once("pong").chain(args.iter().copied()))
+}
+
+#[test]
+fn options_change_the_preset() {
+	let args = parse(&[
+		"--preset",
+		"tournament",
+		"--target-score",
+		"5",
+		"--serve",
+		"loser",
+		"--spin",
+		"--power-ups",
+	])
+	.unwrap();
+	let preset = args.preset.preset();
+	let tournament = Preset::find("tournament").unwrap();
+
+	assert_eq!(preset.name, "Tournament");
+	assert_eq!(preset.rules.target_score, 5);
+	assert_eq!(preset.rules.win_by, tournament.rules.win_by);
+	assert_eq!(preset.rules.serve, ServeRule::Loser);
+	assert!(preset.physics.spin.is_some());
+	assert!(preset.physics.power_ups.is_some());
+}
+
+#[test]
+fn the_classic_preset_is_played_by_default() {
+	let preset = parse(&[]).unwrap().preset.preset();
+	assert_eq!(preset, Preset::find("classic").unwrap());
+}
+
+#[test]
+fn time_limits_make_a_timed_match() {
+	let preset = parse(&["--time-limit", "90"]).unwrap().preset.preset();
+	assert_eq!(preset.rules.time_limit, Some(Duration::from_secs(90)));
+	assert_eq!(preset.rules.target_score, usize::MAX);
+
+	let preset = parse(&["--time-limit", "90", "--target-score", "5"])
+		.unwrap()
+		.preset
+		.preset();
+	assert_eq!(preset.rules.time_limit, Some(Duration::from_secs(90)));
+	assert_eq!(preset.rules.target_score, 5);
+
+	assert!(parse(&["--time-limit", "0"]).is_err());
+}
+
+#[test]
+fn matches_need_a_target_score() {
+	assert!(parse(&["--target-score", "0"]).is_err());
+	assert!(parse(&["--win-by", "0"]).is_err());
+}
+
+#[test]
+fn unknown_presets_are_refused_by_name() {
+	let error = parse(&["--preset", "squash"])
+		.err()
+		.expect("there's no squash preset")
+		.to_string();
+	assert!(error.contains("squash"), "{}", error);
+	assert!(error.contains("arcade"), "{}", error);
+}
+
+#[test]
+fn seconds_cant_be_negative() {
+	assert_eq!(bevy_pong::cli::seconds("1.5"), Ok(Duration::from_millis(1500)));
+	assert!(bevy_pong::cli::seconds("-1").is_err());
+	assert!(bevy_pong::cli::seconds("soon").is_err());
+}
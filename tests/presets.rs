@@ -0,0 +1,78 @@
+//! The shipped presets, and saving and finding custom ones.
+
+use std::{env, fs, time::Duration};
+
+use bevy_pong::{
+	preset::{Preset, PresetError, Presets},
+	ServeRule,
+};
+
+#[test]
+fn shipped_presets_can_be_played() {
+	let presets = Presets::default();
+	let names: Vec<_> = presets.all().iter().map(|preset| preset.name.as_str()).collect();
+	assert_eq!(names, ["Classic", "Arcade", "Tournament", "Lunchtime"]);
+	assert_eq!(presets.selected().name, "Classic");
+
+	for preset in presets.all() {
+		preset
+			.validate()
+			.unwrap_or_else(|error| panic!("{}: {}", preset.name, error));
+	}
+}
+
+#[test]
+fn tournaments_go_to_eleven_win_by_two() {
+	let mut presets = Presets::default();
+	let tournament = presets.select_named("tournament").expect("tournament is shipped");
+
+	assert_eq!(tournament.rules.target_score, 11);
+	assert_eq!(tournament.rules.win_by, 2);
+	assert_eq!(tournament.rules.serve, ServeRule::Alternate(2));
+}
+
+#[test]
+fn arcade_plays_with_spin_and_power_ups() {
+	let mut presets = Presets::default();
+	let arcade = presets.select_named("arcade").expect("arcade is shipped");
+
+	assert!(arcade.physics.spin.is_some());
+	assert!(arcade.physics.power_ups.is_some());
+}
+
+#[test]
+fn lunchtime_matches_end_when_the_time_runs_out() {
+	let mut presets = Presets::default();
+	let lunchtime = presets.select_named("lunchtime").expect("lunchtime is shipped");
+
+	assert_eq!(lunchtime.rules.time_limit, Some(Duration::from_secs(180)));
+}
+
+#[test]
+fn saved_presets_are_found_by_name() {
+	let directory = env::temp_dir().join(format!("pong-presets-{}", std::process::id()));
+	let preset = Preset {
+		name: "Long Rallies".to_owned(),
+		..Presets::default().selected().clone()
+	};
+	let path = preset.save(&directory).expect("the preset should be saved");
+	assert!(path.ends_with("long-rallies.preset.json"));
+
+	let mut presets = Presets::load(Some(&directory));
+	let found = presets.select_named("long rallies").cloned();
+	fs::remove_dir_all(&directory).ok();
+
+	assert_eq!(found.expect("the saved preset should be found"), preset);
+	assert_eq!(presets.all().len(), 5);
+}
+
+#[test]
+fn unknown_presets_name_the_known_ones() {
+	let error = Presets::default()
+		.select_named("pinball")
+		.expect_err("there's no pinball preset");
+
+	assert!(matches!(error, PresetError::Unknown { .. }));
+	let message = error.to_string();
+	assert!(message.contains("classic, arcade, tournament"), "{}", message);
+}
@@ -0,0 +1,118 @@
+//! Matches played by input scripts, checking the score and everything the ball hit.
+
+use bevy_pong::{
+	headless::ScriptedMatch,
+	script::{InputScript, ScriptKeys, Timeline},
+	CollisionEvent, MatchRules, Player, Score,
+};
+
+/// Steps before the first serve can be taken, the two seconds of waiting at 60 steps a second.
+const WAIT_TICKS: u64 = 120;
+
+/// The same timeline for both players, as the coin toss for the first serve can go either way.
+fn both(timeline: Timeline) -> InputScript {
+	InputScript {
+		left: timeline.clone(),
+		right: timeline,
+	}
+}
+
+/// Standing in the middle and serving straight, then running for the top once the ball is on its way.
+fn serve_and_dodge() -> InputScript {
+	both(
+		Timeline::default()
+			.hold(WAIT_TICKS + 30, ScriptKeys::SERVE)
+			.hold(60 * 60, ScriptKeys::UP),
+	)
+}
+
+fn first_to_one() -> MatchRules {
+	MatchRules {
+		target_score: 1,
+		..MatchRules::default()
+	}
+}
+
+#[test]
+fn dodging_the_serve_concedes_the_point() {
+	let report = ScriptedMatch {
+		script: serve_and_dodge(),
+		rules: first_to_one(),
+		..ScriptedMatch::default()
+	}
+	.run();
+
+	let winner = report.winner.expect("the match should be over");
+	let expected_score = match winner {
+		Player::Left => Score { left: 1, right: 0 },
+		Player::Right => Score { left: 0, right: 1 },
+	};
+	assert_eq!(report.score, expected_score);
+	assert!(
+		matches!(report.collisions.as_slice(), [CollisionEvent::Goal { scorer, .. }] if *scorer == winner),
+		"expected a single goal by {:?}, got {:?}",
+		winner,
+		report.collisions
+	);
+}
+
+#[test]
+fn straight_serves_between_still_paddles_are_returned_forever() {
+	let report = ScriptedMatch {
+		script: both(Timeline::default().hold(WAIT_TICKS + 30, ScriptKeys::SERVE)),
+		rules: first_to_one(),
+		max_ticks: 60 * 20,
+		..ScriptedMatch::default()
+	}
+	.run();
+
+	assert_eq!(report.winner, None);
+	assert_eq!(report.score, Score::default());
+	assert!(report.collisions.len() >= 5, "{:?}", report.collisions);
+	assert!(
+		report
+			.collisions
+			.iter()
+			.all(|collision| matches!(collision, CollisionEvent::Paddle { .. })),
+		"{:?}",
+		report.collisions
+	);
+}
+
+#[test]
+fn captured_input_plays_the_same_match_again() {
+	let played = ScriptedMatch {
+		seed: 7,
+		script: serve_and_dodge(),
+		rules: first_to_one(),
+		..ScriptedMatch::default()
+	}
+	.run();
+	assert_eq!(played.captured.left.ticks(), played.ticks);
+	assert_eq!(played.captured.right.ticks(), played.ticks);
+
+	let replayed = ScriptedMatch {
+		seed: 7,
+		script: played.captured.clone(),
+		rules: first_to_one(),
+		..ScriptedMatch::default()
+	}
+	.run();
+	assert_eq!(replayed.score, played.score);
+	assert_eq!(replayed.collisions, played.collisions);
+	assert_eq!(replayed.captured, played.captured);
+}
+
+#[test]
+fn timelines_hold_nothing_once_they_run_out() {
+	let timeline = Timeline::default()
+		.hold(2, ScriptKeys::UP)
+		.hold(1, ScriptKeys::UP)
+		.hold(3, ScriptKeys::DOWN);
+
+	assert_eq!(timeline.ticks(), 6);
+	assert_eq!(timeline.keys(0), ScriptKeys::UP);
+	assert_eq!(timeline.keys(2), ScriptKeys::UP);
+	assert_eq!(timeline.keys(3), ScriptKeys::DOWN);
+	assert_eq!(timeline.keys(6), ScriptKeys::NONE);
+}